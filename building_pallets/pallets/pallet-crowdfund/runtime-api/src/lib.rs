@@ -0,0 +1,55 @@
+//! Runtime API definition for the crowdfund pallet.
+//!
+//! Lets clients query derived crowdfund state without recomputing it from raw storage.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+pub use pallet_crowdfund::{Category, FundIndex, FundInfo};
+use sp_runtime::{DispatchError, Perbill};
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+    pub trait CrowdfundApi<AccountId, Balance, BlockNumber> where
+        AccountId: Codec,
+        Balance: Codec,
+        BlockNumber: Codec,
+    {
+        /// The fraction of a fund's goal raised so far, capped at 100%.
+        fn goal_progress(index: FundIndex) -> Option<Perbill>;
+
+        /// Whether `create` would accept `goal` and `end` at the current block. Nothing is
+        /// created.
+        fn validate_create(goal: Balance, end: BlockNumber) -> Result<(), DispatchError>;
+
+        /// How many blocks until a fund stops accepting contributions, zero once it has.
+        fn blocks_until_end(index: FundIndex) -> Option<BlockNumber>;
+
+        /// How many blocks until a fund can be dissolved, zero once it can.
+        fn blocks_until_dissolvable(index: FundIndex) -> Option<BlockNumber>;
+
+        /// The total raised by a fund so far, or zero if it does not exist.
+        fn raised(index: FundIndex) -> Balance;
+
+        /// Up to `limit` funds that have not been dispensed or dissolved, starting at index
+        /// `start`, along with the `start` of the next page if there is one. A page may be
+        /// short, or empty, when it runs into many removed funds.
+        fn active_funds(
+            start: FundIndex,
+            limit: u32,
+        ) -> (Vec<(FundIndex, FundInfo<AccountId, Balance, BlockNumber>)>, Option<FundIndex>);
+
+        /// The funds in `category` that have not been dispensed, dissolved or slashed.
+        fn funds_in_category(category: Category) -> Vec<FundIndex>;
+
+        /// Whether `who` currently has a contribution recorded in the fund.
+        fn has_contributed(index: FundIndex, who: AccountId) -> bool;
+
+        /// The prefixed storage key of a fund's contributions child trie, for reading it
+        /// through the child state RPCs.
+        fn child_trie_key(index: FundIndex) -> Vec<u8>;
+
+        /// The largest `limit` for `refund_all` whose batch fits within `remaining` weight.
+        fn refund_limit(remaining: u64) -> u32;
+    }
+}
@@ -1,26 +1,127 @@
-//! Benchmarking setup for pallet-template
+//! Benchmarking setup for pallet-crowdfund
 
 use super::*;
 
+use frame_benchmarking::{account, benchmarks, impl_benchmark_test_suite};
+use frame_support::{
+    sp_runtime::traits::{Bounded, Saturating},
+    traits::{Currency, EnsureOrigin, Get, OnInitialize},
+};
 use frame_system::RawOrigin;
-use frame_benchmarking::{benchmarks, whitelisted_caller, impl_benchmark_test_suite};
-use sp_std::{vec, vec::Vec, boxed::Box};
+use sp_std::vec;
 
 #[allow(unused)]
-use crate::Module as Template;
+use crate::Module as PalletCrowdfund;
+
+const SEED: u32 = 0;
+
+type BalanceOf<T> =
+    <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+/// An account with enough free balance to create funds and contribute to them.
+fn funded_account<T: Config>(name: &'static str, index: u32) -> T::AccountId {
+    let who: T::AccountId = account(name, index, SEED);
+    T::Currency::make_free_balance_be(&who, BalanceOf::<T>::max_value() / 1_000_000u32.into());
+    who
+}
+
+/// Create fund 0 and have `contributors` distinct accounts contribute to it. The goal is out of
+/// reach of even `MaxContributors` minimal contributions, so the fund fails once it ends.
+fn create_fund_with_contributors<T: Config>(contributors: u32) -> Result<(), &'static str> {
+    let creator = funded_account::<T>("creator", 0);
+    let beneficiary: T::AccountId = account("beneficiary", 0, SEED);
+    let end = frame_system::Module::<T>::block_number() + 100u32.into();
+    PalletCrowdfund::<T>::create(
+        RawOrigin::Signed(creator).into(),
+        beneficiary,
+        T::MinContribution::get()
+            .saturating_mul((T::MaxContributors::get() + 1).into())
+            .max(T::MinGoal::get()),
+        end,
+        FundParams::default(),
+    )
+    .map_err(|e| e.error)?;
+
+    for i in 0..contributors {
+        let contributor = funded_account::<T>("contributor", i);
+        PalletCrowdfund::<T>::contribute(
+            RawOrigin::Signed(contributor).into(),
+            0,
+            T::MinContribution::get(),
+            None,
+        )
+        .map_err(|e| e.error)?;
+    }
+    Ok(())
+}
 
 benchmarks! {
-	do_something {
-		let s in 0 .. 100;
-		let caller: T::AccountId = whitelisted_caller();
-	}: _(RawOrigin::Signed(caller), s)
-	verify {
-		assert_eq!(Something::<T>::get(), Some(s));
-	}
+    on_initialize {
+        let d in 0 .. T::MaxAutoDispense::get();
+        let goal = T::MinGoal::get().max(T::MinContribution::get());
+        let end = frame_system::Module::<T>::block_number() + 1u32.into();
+        for i in 0..d {
+            PalletCrowdfund::<T>::create(
+                RawOrigin::Signed(funded_account::<T>("creator", i)).into(),
+                account("beneficiary", i, SEED),
+                goal,
+                end,
+                FundParams::default(),
+            )
+            .map_err(|e| e.error)?;
+            PalletCrowdfund::<T>::contribute(
+                RawOrigin::Signed(funded_account::<T>("contributor", i)).into(),
+                i,
+                goal,
+                None,
+            )
+            .map_err(|e| e.error)?;
+        }
+        frame_system::Module::<T>::set_block_number(end);
+    }: {
+        PalletCrowdfund::<T>::on_initialize(end);
+    }
+    verify {
+        for i in 0..d {
+            assert!(PalletCrowdfund::<T>::funds(i).is_none());
+        }
+    }
+
+    refund_all {
+        let c in 0 .. T::MaxContributors::get();
+        create_fund_with_contributors::<T>(c)?;
+        let end = PalletCrowdfund::<T>::funds(0).ok_or("fund was not created")?.end;
+        frame_system::Module::<T>::set_block_number(end + 1u32.into());
+        let caller = funded_account::<T>("caller", 0);
+    }: _(RawOrigin::Signed(caller), 0, Some(c))
+    verify {
+        assert_eq!(PalletCrowdfund::<T>::contributor_count(0), 0);
+    }
+
+    dissolve {
+        let c in 0 .. T::MaxContributors::get();
+        create_fund_with_contributors::<T>(c)?;
+        let end = PalletCrowdfund::<T>::funds(0).ok_or("fund was not created")?.end;
+        frame_system::Module::<T>::set_block_number(end + T::RetirementPeriod::get());
+        let caller = funded_account::<T>("caller", 0);
+    }: _(RawOrigin::Signed(caller), 0)
+    verify {
+        assert!(PalletCrowdfund::<T>::funds(0).is_none());
+    }
+
+    report_and_slash {
+        let c in 1 .. T::MaxContributors::get();
+        create_fund_with_contributors::<T>(c)?;
+        let origin = T::AdminOrigin::successful_origin();
+    }: _<T::Origin>(origin, 0)
+    verify {
+        assert!(PalletCrowdfund::<T>::funds(0).is_none());
+        assert_eq!(PalletCrowdfund::<T>::contributor_count(0), 0);
+    }
 }
 
 impl_benchmark_test_suite!(
-	Template,
-	crate::mock::new_test_ext(),
-	crate::mock::Test,
+    PalletCrowdfund,
+    crate::mock::new_test_ext(),
+    crate::mock::Test,
 );
@@ -16,23 +16,100 @@ mod mock;
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+pub mod weights;
+pub use weights::WeightInfo;
+
+/// Something that reacts to a crowdfund succeeding, e.g. to notify a treasury or record
+/// reputation.
+pub trait OnFundSuccess<AccountId, Balance> {
+    /// Called once `beneficiary` has been paid the `raised` amount of a dispensed fund.
+    fn on_success(beneficiary: &AccountId, raised: Balance);
+}
+
+impl<AccountId, Balance> OnFundSuccess<AccountId, Balance> for () {
+    fn on_success(_: &AccountId, _: Balance) {}
+}
+
+/// Something that decides the deposit a creator must place for a fund with a given goal.
+pub trait DepositFor<Balance> {
+    /// The deposit required to create a fund aiming to raise `goal`.
+    fn deposit_for(goal: Balance) -> Balance;
+}
+
+/// Charges the same deposit for every fund, whatever its goal.
+pub struct FixedDeposit<Deposit>(sp_std::marker::PhantomData<Deposit>);
+impl<Balance, Deposit: frame_support::traits::Get<Balance>> DepositFor<Balance>
+    for FixedDeposit<Deposit>
+{
+    fn deposit_for(_: Balance) -> Balance {
+        Deposit::get()
+    }
+}
+
+/// Something that decides what happens to whatever is left in a fund's pot when it is
+/// dissolved.
+pub trait HandleDissolveResidue<AccountId, Imbalance> {
+    /// Take care of the `residue` of a fund dissolved by `dissolver`.
+    fn handle(dissolver: &AccountId, residue: Imbalance);
+}
+
+/// Hands the residue to whoever dissolved the fund, as a reward for clearing it out of storage.
+pub struct RewardDissolver<Currency>(sp_std::marker::PhantomData<Currency>);
+impl<AccountId, C: frame_support::traits::Currency<AccountId>>
+    HandleDissolveResidue<AccountId, C::NegativeImbalance> for RewardDissolver<C>
+{
+    fn handle(dissolver: &AccountId, residue: C::NegativeImbalance) {
+        C::resolve_creating(dissolver, residue);
+    }
+}
+
+/// Burns the residue, reducing total issuance.
+pub struct BurnResidue;
+impl<AccountId, Imbalance> HandleDissolveResidue<AccountId, Imbalance> for BurnResidue {
+    fn handle(_: &AccountId, _: Imbalance) {}
+}
+
+/// Sends the residue to an `OnUnbalanced` handler, such as a treasury.
+pub struct ResidueTo<Handler>(sp_std::marker::PhantomData<Handler>);
+impl<AccountId, Imbalance, Handler: frame_support::traits::OnUnbalanced<Imbalance>>
+    HandleDissolveResidue<AccountId, Imbalance> for ResidueTo<Handler>
+{
+    fn handle(_: &AccountId, residue: Imbalance) {
+        Handler::on_unbalanced(residue);
+    }
+}
+
 #[frame_support::pallet]
 pub mod pallet {
     use super::*;
     use frame_support::{
+        dispatch::{DispatchResult, WithPostDispatchInfo},
         ensure,
         pallet_prelude::*,
         sp_runtime::{
-            traits::{AccountIdConversion, Hash, Saturating, Zero},
-            ModuleId,
+            traits::{
+                AccountIdConversion, CheckedAdd, Hash, One, SaturatedConversion, Saturating, Zero,
+            },
+            ModuleId, PerThing, Perbill,
+        },
+        storage::{child, unhashed},
+        traits::{
+            BalanceStatus, Currency, EnsureOrigin, ExistenceRequirement, Get, OnUnbalanced,
+            ReservableCurrency, VestingSchedule, WithdrawReasons,
         },
-        storage::child,
-        traits::{Currency, ExistenceRequirement, Get, ReservableCurrency, WithdrawReasons},
+        transactional,
     };
     use frame_system::{ensure_signed, pallet_prelude::*};
 
     const PALLET_ID: ModuleId = ModuleId(*b"ex/cfund");
 
+    /// How many fund indices `active_funds` may read for each fund it is asked to return, so
+    /// that long runs of removed funds cannot make a single page unbounded.
+    const ACTIVE_FUNDS_SCAN_FACTOR: u32 = 4;
+
     // Simple declaration of the `Pallet` type. It is a placeholder we use
     // to implement traits and methods.
     #[pallet::pallet]
@@ -48,16 +125,80 @@ pub mod pallet {
         /// The currency in which the crowdfunds will be denominated
         type Currency: ReservableCurrency<Self::AccountId>;
 
-        /// The amount to be held on deposit by the owner of a crowdfund
-        type SubmissionDeposit: Get<BalanceOf<Self>>;
+        /// The amount to be held on deposit by the owner of a crowdfund, given its goal. Use
+        /// `FixedDeposit` for a flat deposit.
+        ///
+        /// The deposit is what first brings the fund's pot account into existence, so it must be
+        /// at least the currency's existential deposit. `create` and `validate_create` fail with
+        /// `DepositTooLow` for any goal it would be smaller for.
+        type DepositCalculator: DepositFor<BalanceOf<Self>>;
 
         /// The minimum amount that may be contributed into a crowdfund. Should almost certainly be at
         /// least ExistentialDeposit.
         type MinContribution: Get<BalanceOf<Self>>;
 
+        /// The smallest goal a fund may be created with or adjusted to.
+        type MinGoal: Get<BalanceOf<Self>>;
+
+        /// Whether a contribution may take the contributor's balance below the existential
+        /// deposit and reap their account. When `false`, such contributions fail instead, which
+        /// is the safer choice.
+        type AllowContributorDeath: Get<bool>;
+
+        /// Whether a fund frozen with `set_fund_frozen` still accepts contributions. Paying
+        /// out of a frozen fund is never allowed.
+        type AllowContributionsWhileFrozen: Get<bool>;
+
+        /// The minimum number of blocks between two funds created by the same account.
+        type CreationCooldown: Get<Self::BlockNumber>;
+
+        /// The longest a fund may stay open for contributions, in blocks from its creation.
+        type MaxDuration: Get<Self::BlockNumber>;
+
         /// The period of time (in blocks) after an unsuccessful crowdfund ending during which
         /// contributors are able to withdraw their funds. After this period, their funds are lost.
         type RetirementPeriod: Get<Self::BlockNumber>;
+
+        /// The share of every contribution kept by the fund as a fee. It counts towards `raised`
+        /// but is never refunded to the contributor.
+        type ContributionFee: Get<Perbill>;
+
+        /// The maximum number of secondary beneficiaries a fund may split its payout with.
+        type MaxSplits: Get<u32>;
+
+        /// The maximum number of stretch goals a fund may define.
+        type MaxStretchGoals: Get<u32>;
+
+        /// Vesting schedules used to release a dispensed payout to the beneficiary gradually
+        type VestingSchedule: VestingSchedule<
+            Self::AccountId,
+            Moment = Self::BlockNumber,
+            Currency = Self::Currency,
+        >;
+
+        /// Handler called whenever a successful fund is dispensed
+        type OnSuccess: OnFundSuccess<Self::AccountId, BalanceOf<Self>>;
+
+        /// The origin allowed to slash abusive funds
+        type AdminOrigin: EnsureOrigin<Self::Origin>;
+
+        /// Handler for the deposit seized from a slashed fund
+        type OnSlash: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+        /// What happens to the deposit and any unclaimed contributions of a dissolved fund. Use
+        /// `RewardDissolver` to pay them to the dissolver.
+        type DissolveDestination: HandleDissolveResidue<Self::AccountId, NegativeImbalanceOf<Self>>;
+
+        /// The maximum number of distinct contributors to a single fund. This bounds the work
+        /// needed to refund every contributor at once.
+        type MaxContributors: Get<u32>;
+
+        /// The maximum number of ended funds considered for automatic dispensing in one block.
+        /// Any beyond this are carried over to the following blocks.
+        type MaxAutoDispense: Get<u32>;
+
+        /// Weight information for extrinsics in this pallet.
+        type WeightInfo: WeightInfo;
     }
 
     /// Simple index for identifying a fund.
@@ -65,22 +206,143 @@ pub mod pallet {
 
     type AccountIdOf<T> = <T as frame_system::Config>::AccountId;
     type BalanceOf<T> = <<T as Config>::Currency as Currency<AccountIdOf<T>>>::Balance;
+    type NegativeImbalanceOf<T> =
+        <<T as Config>::Currency as Currency<AccountIdOf<T>>>::NegativeImbalance;
     type FundInfoOf<T> =
         FundInfo<AccountIdOf<T>, BalanceOf<T>, <T as frame_system::Config>::BlockNumber>;
+    type FundParamsOf<T> =
+        FundParams<AccountIdOf<T>, BalanceOf<T>, <T as frame_system::Config>::BlockNumber>;
+    type HeldPayoutOf<T> =
+        HeldPayout<AccountIdOf<T>, BalanceOf<T>, <T as frame_system::Config>::BlockNumber>;
 
-    #[derive(Encode, Decode, Default, PartialEq, Eq)]
+    #[derive(Encode, Decode, Default, Clone, PartialEq, Eq)]
     #[cfg_attr(feature = "std", derive(Debug))]
     pub struct FundInfo<AccountId, Balance, BlockNumber> {
+        /// The account that created the fund and placed the deposit
+        pub creator: AccountId,
         /// The account that will recieve the funds if the campaign is successful
-        beneficiary: AccountId,
+        pub beneficiary: AccountId,
         /// The amount of deposit placed
-        deposit: Balance,
+        pub deposit: Balance,
         /// The total amount raised
-        raised: Balance,
+        pub raised: Balance,
+        /// The part of `raised` taken as contribution fees, which is never refunded
+        pub fees: Balance,
+        /// Block number at which the fund was created
+        pub created: BlockNumber,
         /// Block number after which funding must have succeeded
-        end: BlockNumber,
+        pub end: BlockNumber,
         /// Upper bound on `raised`
-        goal: Balance,
+        pub goal: Balance,
+        /// If set, the fund counts as successful once `raised` reaches this instead of `goal`
+        pub soft_cap: Option<Balance>,
+        /// Targets above `goal`, in ascending order, that unlock additional deliverables
+        pub stretch_goals: Vec<Balance>,
+        /// How many of the `stretch_goals` have been reached so far
+        pub stretch_reached: u32,
+        /// If set, the fund only succeeds with at least this many distinct contributors
+        pub min_contributors: Option<u32>,
+        /// Secondary beneficiaries and their share of the payout. Whatever is left after
+        /// paying them out goes to `beneficiary`.
+        pub splits: Vec<(AccountId, Perbill)>,
+        /// If set, the beneficiary's payout is locked and released linearly over this many blocks.
+        /// If the beneficiary already vests when the fund is dispensed, the pot holds the payout
+        /// back instead, see `HeldPayout`.
+        pub vesting_blocks: Option<BlockNumber>,
+        /// What kind of campaign this is, for discovery
+        pub category: Category,
+        /// If set, this fund's own minimum contribution, never below `MinContribution`
+        pub min_contribution: Option<Balance>,
+        /// Whether each contribution emits its own event. If not, a `ContributionSummary` is
+        /// emitted once per block in which the fund received contributions.
+        pub emit_contributions: bool,
+    }
+
+    /// The optional settings of a new fund, each documented on the `FundInfo` field of the same
+    /// name. The default configures none of them.
+    #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+    pub struct FundParams<AccountId, Balance, BlockNumber> {
+        pub splits: Vec<(AccountId, Perbill)>,
+        pub vesting_blocks: Option<BlockNumber>,
+        pub soft_cap: Option<Balance>,
+        pub stretch_goals: Vec<Balance>,
+        pub min_contributors: Option<u32>,
+        pub category: Category,
+        pub min_contribution: Option<Balance>,
+        pub emit_contributions: bool,
+    }
+
+    impl<AccountId, Balance, BlockNumber> Default for FundParams<AccountId, Balance, BlockNumber> {
+        fn default() -> Self {
+            FundParams {
+                splits: Vec::new(),
+                vesting_blocks: None,
+                soft_cap: None,
+                stretch_goals: Vec::new(),
+                min_contributors: None,
+                category: Category::default(),
+                min_contribution: None,
+                emit_contributions: true,
+            }
+        }
+    }
+
+    /// A vested payout kept in a fund's pot because the beneficiary already had a vesting
+    /// schedule, and only one is supported per account. It is released at the same rate with
+    /// `release_vested`.
+    #[derive(Encode, Decode, Default, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(Debug))]
+    pub struct HeldPayout<AccountId, Balance, BlockNumber> {
+        /// The account the payout is released to
+        pub beneficiary: AccountId,
+        /// What the pot still holds for the beneficiary
+        pub remaining: Balance,
+        /// How much is released per block
+        pub per_block: Balance,
+        /// The block up to which the payout has been released
+        pub released_at: BlockNumber,
+    }
+
+    /// Funds a sponsor has put up to match contributions to a fund.
+    #[derive(Encode, Decode, Default, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(Debug))]
+    pub struct MatchInfo<AccountId, Balance> {
+        /// The account whose funds are reserved for matching
+        pub sponsor: AccountId,
+        /// The share of each contribution that is matched
+        pub ratio: Perbill,
+        /// The most the sponsor committed
+        pub cap: Balance,
+        /// What is still reserved and available for matching
+        pub remaining: Balance,
+        /// What contributions have actually drawn into the pot so far
+        pub drawn: Balance,
+    }
+
+    /// The kind of campaign a fund is running, so explorers can filter by it.
+    #[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+    pub enum Category {
+        Other,
+        Art,
+        Tech,
+        Charity,
+    }
+
+    impl Default for Category {
+        fn default() -> Self {
+            Category::Other
+        }
+    }
+
+    /// Why a contribution was paid back to its contributor.
+    #[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+    pub enum RefundReason {
+        /// The contributor withdrew it themselves
+        Voluntary,
+        /// It was swept back in a batch after the fund failed
+        Sweep,
+        /// The fund was slashed by the admin origin
+        Slash,
     }
 
     #[pallet::storage]
@@ -94,16 +356,108 @@ pub mod pallet {
     /// The total number of funds that have so far been allocated.
     pub(super) type FundCount<T: Config> = StorageValue<_, FundIndex, ValueQuery>;
 
+    #[pallet::storage]
+    /// Client references already used by each contributor to a fund. Only populated when a
+    /// contribution supplies a reference, so that resubmitted transactions can be rejected.
+    /// Keyed by fund first, so that a fund's references are cleared together when it is removed.
+    pub(super) type ContributionRefs<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        FundIndex,
+        Blake2_128Concat,
+        (T::AccountId, u64),
+        (),
+        OptionQuery,
+    >;
+
+    #[pallet::storage]
+    #[pallet::getter(fn contributor_count)]
+    /// The number of accounts with a contribution recorded in each fund.
+    pub(super) type ContributorCount<T: Config> =
+        StorageMap<_, Blake2_128Concat, FundIndex, u32, ValueQuery>;
+
+    #[pallet::storage]
+    /// The funds ending at each block, to be dispensed automatically if successful.
+    pub(super) type EndingAt<T: Config> =
+        StorageMap<_, Twox64Concat, T::BlockNumber, Vec<FundIndex>, ValueQuery>;
+
+    #[pallet::storage]
+    /// Ended funds still waiting to be considered for automatic dispensing because of
+    /// `MaxAutoDispense`.
+    pub(super) type DispenseQueue<T: Config> = StorageValue<_, Vec<FundIndex>, ValueQuery>;
+
+    #[pallet::storage]
+    /// The funds in each category, for enumerating them without going through every fund.
+    pub(super) type FundsByCategory<T: Config> =
+        StorageDoubleMap<_, Twox64Concat, Category, Twox64Concat, FundIndex, (), OptionQuery>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn unclaimed_deposit)]
+    /// The creator and deposit of each dispensed fund whose deposit has not been claimed yet.
+    pub(super) type UnclaimedDeposits<T: Config> =
+        StorageMap<_, Blake2_128Concat, FundIndex, (T::AccountId, BalanceOf<T>), OptionQuery>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn unclaimed_private)]
+    /// The private contributions still held in the pot of each slashed fund. The fund itself is
+    /// gone, but its private contributions stay claimable with `withdraw_private`.
+    pub(super) type UnclaimedPrivate<T: Config> =
+        StorageMap<_, Blake2_128Concat, FundIndex, BalanceOf<T>, OptionQuery>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn held_payout)]
+    /// The vested payouts still held in the pot of each dispensed fund whose beneficiary
+    /// already had a vesting schedule.
+    pub(super) type HeldPayouts<T: Config> =
+        StorageMap<_, Blake2_128Concat, FundIndex, HeldPayoutOf<T>, OptionQuery>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn last_created)]
+    /// The block at which each account last created a fund.
+    ///
+    /// Only needed while the cooldown can still apply: the entry is pruned when one of the
+    /// account's funds is removed after its cooldown has passed. An entry that outlives all of
+    /// the account's funds is bounded by one per creator, and each creation paid a deposit.
+    pub(super) type LastCreated<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, T::BlockNumber, OptionQuery>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn match_pool)]
+    /// The matching funds a sponsor has committed to each fund, reserved on the sponsor's
+    /// account until they are drawn.
+    pub(super) type Matches<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        FundIndex,
+        MatchInfo<T::AccountId, BalanceOf<T>>,
+        OptionQuery,
+    >;
+
+    #[pallet::storage]
+    #[pallet::getter(fn is_frozen)]
+    /// Funds the admin origin has frozen, e.g. while they are investigated. Nothing can be paid
+    /// out of a frozen fund.
+    pub(super) type Frozen<T: Config> =
+        StorageMap<_, Blake2_128Concat, FundIndex, bool, ValueQuery>;
+
+    #[pallet::storage]
+    /// Funds that received contributions this block without emitting an event for each, see
+    /// `FundInfo::emit_contributions`. Cleared at the end of every block.
+    pub(super) type PendingSummaries<T: Config> = StorageValue<_, Vec<FundIndex>, ValueQuery>;
+
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
     #[pallet::metadata(BalanceOf<T> = "Balance", AccountIdOf<T> = "AccountId", BlockNumber<T> = "BlockNumber")]
     pub enum Event<T: Config> {
         Created(FundIndex, <T as frame_system::Config>::BlockNumber),
+        /// A contribution was made. Carries the contributor's total contribution and whether
+        /// this was their first contribution to the fund.
         Contributed(
             <T as frame_system::Config>::AccountId,
             FundIndex,
             BalanceOf<T>,
             <T as frame_system::Config>::BlockNumber,
+            bool,
         ),
         Withdrew(
             <T as frame_system::Config>::AccountId,
@@ -116,11 +470,89 @@ pub mod pallet {
             FundIndex,
             <T as frame_system::Config>::BlockNumber,
             <T as frame_system::Config>::AccountId,
+            BalanceOf<T>,
         ),
         Dispensed(
             FundIndex,
             <T as frame_system::Config>::BlockNumber,
             <T as frame_system::Config>::AccountId,
+            BalanceOf<T>,
+        ),
+        /// A fund's vested payout is held in its pot, because its beneficiary already has a
+        /// vesting schedule. Carries the amount held.
+        PayoutHeld(
+            FundIndex,
+            <T as frame_system::Config>::AccountId,
+            BalanceOf<T>,
+        ),
+        /// Part of a held payout was released to the beneficiary.
+        PayoutReleased(
+            FundIndex,
+            <T as frame_system::Config>::AccountId,
+            BalanceOf<T>,
+        ),
+        /// A successful fund could not be dispensed automatically, for the given reason. Nothing
+        /// was paid out, and the fund is left for a manual `dispense`.
+        AutoDispenseFailed(FundIndex, DispatchError),
+        /// A fund's raised amount crossed the stretch goal at the given position.
+        StretchReached(FundIndex, u32, <T as frame_system::Config>::BlockNumber),
+        /// Contributors of a failed fund were refunded in a batch. Carries the number refunded
+        /// and whether any contributions remain.
+        BatchRefunded(FundIndex, u32, bool),
+        /// A fund was shut down by the admin origin. Carries the amount seized: the deposit plus
+        /// any contribution fees.
+        Slashed(
+            FundIndex,
+            <T as frame_system::Config>::BlockNumber,
+            BalanceOf<T>,
+        ),
+        /// A contribution was paid back to its contributor, for the given reason.
+        ContributionRefunded(
+            <T as frame_system::Config>::AccountId,
+            FundIndex,
+            BalanceOf<T>,
+            RefundReason,
+            <T as frame_system::Config>::BlockNumber,
+        ),
+        /// A contribution was made under a commitment rather than an account. Carries the total
+        /// recorded under the commitment.
+        ContributedPrivately(
+            FundIndex,
+            <T as frame_system::Config>::Hash,
+            BalanceOf<T>,
+            <T as frame_system::Config>::BlockNumber,
+        ),
+        /// A sponsor committed funds to match contributions to a fund, up to a cap.
+        MatchAdded(FundIndex, AccountIdOf<T>, Perbill, BalanceOf<T>),
+        /// A contribution drew this much from the fund's matching funds.
+        Matched(FundIndex, AccountIdOf<T>, BalanceOf<T>),
+        /// The matching funds no longer needed by a fund went back to the sponsor.
+        MatchReleased(FundIndex, AccountIdOf<T>, BalanceOf<T>),
+        /// The fund's totals at the end of a block in which it received contributions, in place
+        /// of an event per contribution: the amount raised and the number of contributors.
+        ContributionSummary(
+            FundIndex,
+            BalanceOf<T>,
+            u32,
+            <T as frame_system::Config>::BlockNumber,
+        ),
+        /// What was left in a dispensed fund's pot beyond its deposit went to the creator.
+        DustSwept(FundIndex, AccountIdOf<T>, BalanceOf<T>),
+        /// The admin origin froze or unfroze a fund.
+        FrozenChanged(FundIndex, bool),
+        /// The creator changed the fund's goal before anyone contributed.
+        GoalAdjusted(FundIndex, BalanceOf<T>),
+        /// The creator of a dispensed fund took back their deposit.
+        DepositClaimed(
+            FundIndex,
+            <T as frame_system::Config>::AccountId,
+            BalanceOf<T>,
+        ),
+        /// The creator redirected the fund's payout from the first account to the second.
+        BeneficiaryChanged(
+            FundIndex,
+            <T as frame_system::Config>::AccountId,
+            <T as frame_system::Config>::AccountId,
         ),
     }
 
@@ -128,8 +560,14 @@ pub mod pallet {
     pub enum Error<T> {
         /// Crowdfund must end after it starts
         EndTooEarly,
+        /// Crowdfund must end within `MaxDuration` of its creation
+        EndTooFar,
+        /// The account created a fund too recently to create another
+        CreationTooSoon,
         /// Must contribute at least the minimum amount of funds
         ContributionTooSmall,
+        /// A contribution of zero is never accepted, whatever the configured minimum
+        ZeroContribution,
         /// The fund index specified does not exist
         InvalidIndex,
         /// The crowdfund's contribution period has ended; no more contributions will be accepted
@@ -140,147 +578,1586 @@ pub mod pallet {
         NoContribution,
         /// You cannot dissolve a fund that has not yet completed its retirement period
         FundNotRetired,
+        /// The fund's retirement period is over, so contributions can no longer be withdrawn
+        RefundPeriodExpired,
         /// Cannot dispense funds from an unsuccessful fund
         UnsuccessfulFund,
+        /// The payout split shares add up to more than the whole
+        InvalidSplit,
+        /// The payout is split between more accounts than allowed
+        TooManySplits,
+        /// The vesting period must be at least one block
+        InvalidVestingPeriod,
+        /// The soft cap must not be above the goal
+        InvalidSoftCap,
+        /// The fund already has the maximum number of contributors
+        TooManyContributors,
+        /// Contributors of a successful fund cannot be refunded, it is dispensed instead
+        SuccessfulFund,
+        /// A contribution with this client reference was already made to the fund
+        DuplicateContribution,
+        /// Stretch goals must be strictly ascending and above the goal
+        InvalidStretchGoals,
+        /// The fund defines more stretch goals than allowed
+        TooManyStretchGoals,
+        /// The contribution would overflow the amount raised
+        Overflow,
+        /// The fund has not been dispensed, or its deposit was already claimed
+        NoDeposit,
+        /// The fund holds no payout back for its beneficiary
+        NoHeldPayout,
+        /// Nothing more of the held payout has vested yet
+        NothingToRelease,
+        /// A fund cannot pay out to its own pot account
+        InvalidBeneficiary,
+        /// The goal is below the configured minimum
+        GoalTooLow,
+        /// A fund's goal can only be lowered
+        GoalNotLowered,
+        /// Only the account that created the fund may do this
+        NotFundCreator,
+        /// The fund has already received contributions, so its terms are fixed
+        ContributionsExist,
+        /// A fund's own minimum contribution cannot be below the configured one
+        FundMinimumTooLow,
+        /// A contribution record could not be decoded
+        CorruptContribution,
+        /// The fund is frozen by the admin origin
+        FundFrozen,
+        /// The deposit is below the existential deposit, so it could not keep the pot alive
+        DepositTooLow,
+        /// A match needs a non-zero ratio and cap
+        InvalidMatch,
+        /// The fund already has a sponsor matching its contributions
+        MatchExists,
+        /// The fund has no matching funds
+        NoMatch,
+        /// Must contribute at least the fund's own minimum amount
+        ContributionBelowFundMinimum,
     }
 
     #[pallet::hooks]
-    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        /// Dispense the successful funds that have ended, up to `MaxAutoDispense` per block.
+        fn on_initialize(now: BlockNumberFor<T>) -> Weight {
+            // `on_finalize` reads and clears the pending summaries. Reading each summarized fund
+            // is left to the contribution that deferred it, which is charged a flat weight.
+            let finalize = T::DbWeight::get().reads_writes(1, 1);
+
+            let mut queue = <DispenseQueue<T>>::get();
+            queue.extend(<EndingAt<T>>::take(now));
+            if queue.is_empty() {
+                return T::WeightInfo::on_initialize(0) + finalize;
+            }
+
+            let count = queue.len().min(T::MaxAutoDispense::get() as usize);
+            let mut rest = queue.split_off(count);
+            for index in queue {
+                // Funds already dispensed by hand are gone and failed funds stay for refunds
+                if let Some(fund) = Self::funds(index) {
+                    if !Self::is_successful(index, &fund) {
+                        continue;
+                    }
+                    // Frozen funds go back in the queue, to be dispensed once they are unfrozen
+                    if Self::is_frozen(index) {
+                        rest.push(index);
+                    } else if let Err(e) = Self::do_dispense(index, &fund.creator) {
+                        Self::deposit_event(Event::AutoDispenseFailed(index, e));
+                    }
+                }
+            }
+            if rest.is_empty() {
+                <DispenseQueue<T>>::kill();
+            } else {
+                <DispenseQueue<T>>::put(rest);
+            }
+
+            T::WeightInfo::on_initialize(count as u32) + finalize
+        }
+
+        /// Emit a `ContributionSummary` for each fund that received contributions this block
+        /// without emitting an event for each.
+        fn on_finalize(now: BlockNumberFor<T>) {
+            let pending = <PendingSummaries<T>>::get();
+            if pending.is_empty() {
+                return;
+            }
+            <PendingSummaries<T>>::kill();
+            for index in pending {
+                if let Some(fund) = Self::funds(index) {
+                    Self::deposit_event(Event::ContributionSummary(
+                        index,
+                        fund.raised,
+                        Self::contributor_count(index),
+                        now,
+                    ));
+                }
+            }
+        }
+    }
 
     #[pallet::call]
     impl<T: Config> Pallet<T> {
-        /// Create a new fund
+        /// Create a new fund, with the optional settings in `params`
         #[pallet::weight(10_000)]
         pub fn create(
             origin: OriginFor<T>,
             beneficiary: AccountIdOf<T>,
             goal: BalanceOf<T>,
             end: T::BlockNumber,
+            params: FundParamsOf<T>,
         ) -> DispatchResultWithPostInfo {
             let creator = ensure_signed(origin)?;
 
-            let now = <frame_system::Module<T>>::block_number();
-            ensure!(end > now, Error::<T>::EndTooEarly);
-            let deposit = T::SubmissionDeposit::get();
+            Self::do_create(creator, beneficiary, goal, end, params)?;
 
-            let imb = T::Currency::withdraw(
-                &creator,
-                deposit,
-                WithdrawReasons::TRANSFER,
-                ExistenceRequirement::AllowDeath,
-            )?;
+            Ok(().into())
+        }
 
-            let index = <FundCount<T>>::get();
-            // not protected against overflow, see safemath section
-            <FundCount<T>>::put(index + 1);
-            // No fees are paid here if we need to create this account; that's why we don't just
-            // use the stock `transfer`.
-            T::Currency::resolve_creating(&Self::fund_account_id(index), imb);
+        /// Create a new fund that ends `duration` blocks from now, with no optional features
+        /// configured. Saves looking up the current block to work out an absolute `end`.
+        #[pallet::weight(10_000)]
+        pub fn create_with_duration(
+            origin: OriginFor<T>,
+            beneficiary: AccountIdOf<T>,
+            goal: BalanceOf<T>,
+            duration: T::BlockNumber,
+        ) -> DispatchResultWithPostInfo {
+            let creator = ensure_signed(origin)?;
 
-            <Funds<T>>::insert(
-                index,
-                FundInfo {
-                    beneficiary,
-                    deposit,
-                    raised: Zero::zero(),
-                    end,
-                    goal,
-                },
-            );
+            ensure!(!duration.is_zero(), Error::<T>::EndTooEarly);
+            // A duration past `MaxDuration` is rejected by `check_create_params` as `EndTooFar`
+            let end = <frame_system::Module<T>>::block_number().saturating_add(duration);
+            Self::do_create(creator, beneficiary, goal, end, FundParams::default())?;
+
+            Ok(().into())
+        }
+
+        /// Create a new fund ending at `end`, with no optional features configured, and seed it
+        /// with a contribution of `initial` from the creator. The contribution is checked like
+        /// any other.
+        ///
+        /// The contribution can still fail after the fund was created, e.g. on the creator's
+        /// balance, so unlike other calls this one is transactional: either both happen or
+        /// neither does.
+        #[pallet::weight(20_000)]
+        #[transactional]
+        pub fn create_and_contribute(
+            origin: OriginFor<T>,
+            beneficiary: AccountIdOf<T>,
+            goal: BalanceOf<T>,
+            end: T::BlockNumber,
+            initial: BalanceOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let creator = ensure_signed(origin)?;
+
+            let index = Self::do_create(
+                creator.clone(),
+                beneficiary,
+                goal,
+                end,
+                FundParams::default(),
+            )?;
+            // The weight refunds of a failed contribution do not apply once a fund was created
+            Self::do_contribute(creator, index, initial, None, None).map_err(|e| e.error)?;
 
-            Self::deposit_event(Event::Created(index, now));
             Ok(().into())
         }
 
         /// Contribute funds to an existing fund    
+        ///
+        /// A relayer that may resubmit the transaction can pass a `client_ref`; a second
+        /// contribution from the same account to the same fund with the same reference is
+        /// rejected rather than moving funds again.
+        ///
+        /// A contribution rejected for its value is refunded all of its weight, and one rejected
+        /// because the fund does not exist is only charged for that lookup.
         #[pallet::weight(10_000)]
-        fn contribute(
+        pub fn contribute(
             origin: OriginFor<T>,
             index: FundIndex,
             value: BalanceOf<T>,
+            client_ref: Option<u64>,
         ) -> DispatchResultWithPostInfo {
             let who = ensure_signed(origin)?;
 
-            ensure!(
-                value >= T::MinContribution::get(),
-                Error::<T>::ContributionTooSmall
-            );
-            let mut fund = Self::funds(index).ok_or(Error::<T>::InvalidIndex)?;
+            Self::do_contribute(who, index, value, client_ref, None)
+        }
 
-            // Make sure crowdfund has not ended
+        /// Contribute to a fund without linking the contribution to an account in the fund's
+        /// records. It is stored under `commitment`, which must be
+        /// `T::Hashing::hash_of(&(account, secret))` for the account that will later withdraw
+        /// it with `withdraw_private`.
+        ///
+        /// This only hides who owns the contribution in the fund's storage. The transfer into
+        /// the pot is still visible on chain. Private contributions are not swept by
+        /// `refund_all`, and stay claimable if the fund is slashed. Otherwise they follow the
+        /// same rules as `contribute`: each commitment counts towards `MaxContributors`, the fee
+        /// is taken and the fund's match applies.
+        #[pallet::weight(10_000)]
+        pub fn contribute_private(
+            origin: OriginFor<T>,
+            index: FundIndex,
+            value: BalanceOf<T>,
+            commitment: T::Hash,
+            client_ref: Option<u64>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_contribute(who, index, value, client_ref, Some(commitment))
+        }
+
+        /// Commit funds to match contributions to a fund: each later contribution draws `ratio`
+        /// of its value from the sponsor, until `cap` has been drawn. The whole cap is reserved
+        /// on the sponsor's account now.
+        ///
+        /// What is not drawn goes back to the sponsor when the fund is dispensed. If the fund
+        /// fails, everything the sponsor put up is returned with `release_match`.
+        #[pallet::weight(10_000)]
+        pub fn add_match(
+            origin: OriginFor<T>,
+            index: FundIndex,
+            ratio: Perbill,
+            cap: BalanceOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let sponsor = ensure_signed(origin)?;
+
+            ensure!(!ratio.is_zero() && !cap.is_zero(), Error::<T>::InvalidMatch);
+            let fund = Self::get_fund(index)?;
             let now = <frame_system::Module<T>>::block_number();
             ensure!(fund.end > now, Error::<T>::ContributionPeriodOver);
+            ensure!(!<Matches<T>>::contains_key(index), Error::<T>::MatchExists);
 
-            // Add contribution to the fund
-            T::Currency::transfer(
-                &who,
-                &Self::fund_account_id(index),
-                value,
-                ExistenceRequirement::AllowDeath,
-            )?;
+            T::Currency::reserve(&sponsor, cap)?;
+            <Matches<T>>::insert(
+                index,
+                MatchInfo {
+                    sponsor: sponsor.clone(),
+                    ratio,
+                    cap,
+                    remaining: cap,
+                    drawn: Zero::zero(),
+                },
+            );
 
-            fund.raised += value;
-            Funds::<T>::insert(index, &fund);
+            Self::deposit_event(Event::MatchAdded(index, sponsor, ratio, cap));
+
+            Ok(().into())
+        }
+
+        /// Return the matching funds of a fund that ended without reaching its goal to the
+        /// sponsor, including what contributions already drew into the pot. Anyone may call
+        /// this.
+        #[pallet::weight(10_000)]
+        pub fn release_match(origin: OriginFor<T>, index: FundIndex) -> DispatchResultWithPostInfo {
+            ensure_signed(origin)?;
 
-            let balance = Self::contribution_get(index, &who);
-            let balance = balance.saturating_add(value);
-            Self::contribution_put(index, &who, &balance);
+            let mut fund = Self::get_fund(index)?;
+            Self::ensure_not_frozen(index)?;
+            let now = <frame_system::Module<T>>::block_number();
+            ensure!(fund.end < now, Error::<T>::FundStillActive);
+            ensure!(
+                !Self::is_successful(index, &fund),
+                Error::<T>::SuccessfulFund
+            );
+            ensure!(<Matches<T>>::contains_key(index), Error::<T>::NoMatch);
 
-            Self::deposit_event(Event::Contributed(who, index, balance, now));
+            let pot = Self::fund_account_id(index);
+            Self::settle_match(index, &pot, &mut fund, true)?;
+            <Funds<T>>::insert(index, &fund);
 
             Ok(().into())
         }
-    }
 
-    impl<T: Config> Pallet<T> {
-        /// The account ID of the fund pot.
+        /// Withdraw a private contribution, proving ownership with the `secret` its commitment
+        /// was made from. The commitment is rehashed from the caller's account and `secret`, so
+        /// a secret seen in a pending transaction is of no use to anyone else.
         ///
-        /// This actually does computation. If you need to keep using it, then make sure you cache the
-        /// value and only call this once.
-        pub fn fund_account_id(index: FundIndex) -> T::AccountId {
-            let res = PALLET_ID.into_sub_account(index);
-            debug::info!("fund_account_id: {:?}", &res);
-            res
-        }
+        /// Also works on a fund that was slashed, for as long as it has unclaimed private
+        /// contributions.
+        #[pallet::weight(10_000)]
+        pub fn withdraw_private(
+            origin: OriginFor<T>,
+            index: FundIndex,
+            secret: Vec<u8>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
 
-        /// Find the ID associated with the fund
-        ///
-        /// Each fund stores information about its contributors and their contributions in a child trie
-        /// This helper function calculates the id of the associated child trie.
-        pub fn id_from_index(index: FundIndex) -> child::ChildInfo {
-            let mut buf = Vec::new();
-            buf.extend_from_slice(b"crowdfnd");
-            buf.extend_from_slice(&index.to_le_bytes()[..]);
+            if let Some(unclaimed) = Self::unclaimed_private(index) {
+                return Self::claim_slashed_private(who, index, secret, unclaimed);
+            }
 
-            child::ChildInfo::new_default(T::Hashing::hash(&buf).as_ref())
-        }
+            let mut fund = Self::get_fund(index)?;
+            Self::ensure_not_frozen(index)?;
+            let now = <frame_system::Module<T>>::block_number();
+            ensure!(fund.end < now, Error::<T>::FundStillActive);
+            ensure!(
+                now < fund.end + T::RetirementPeriod::get(),
+                Error::<T>::RefundPeriodExpired
+            );
+            ensure!(
+                !Self::is_successful(index, &fund),
+                Error::<T>::SuccessfulFund
+            );
 
-        /// Record a contribution in the associated child trie.
-        pub fn contribution_put(index: FundIndex, who: &T::AccountId, balance: &BalanceOf<T>) {
-            let id = Self::id_from_index(index);
-            who.using_encoded(|b| child::put(&id, b, &balance));
-        }
+            let commitment = T::Hashing::hash_of(&(&who, &secret));
+            let balance = Self::private_contribution_try_get(index, &commitment)?
+                .ok_or(Error::<T>::NoContribution)?;
 
-        /// Lookup a contribution in the associated child trie.
-        pub fn contribution_get(index: FundIndex, who: &T::AccountId) -> BalanceOf<T> {
-            let id = Self::id_from_index(index);
-            who.using_encoded(|b| child::get_or_default::<BalanceOf<T>>(&id, b))
+            let pot = Self::fund_account_id(index);
+            Self::pay_from_pot(&pot, &who, balance)?;
+
+            Self::private_contribution_kill(index, &commitment);
+            <ContributorCount<T>>::mutate(index, |count| *count = count.saturating_sub(1));
+            fund.raised = fund.raised.saturating_sub(balance);
+            <Funds<T>>::insert(index, &fund);
+
+            Self::deposit_event(Event::ContributionRefunded(
+                who,
+                index,
+                balance,
+                RefundReason::Voluntary,
+                now,
+            ));
+
+            Ok(().into())
         }
 
-        /// Remove a contribution from an associated child trie.
-        pub fn contribution_kill(index: FundIndex, who: &T::AccountId) {
-            let id = Self::id_from_index(index);
-            who.using_encoded(|b| child::kill(&id, b));
+        /// Lower the goal of a fund. Only the creator may do this, and only while the fund is
+        /// still open and nobody has contributed yet.
+        #[pallet::weight(10_000)]
+        pub fn adjust_goal(
+            origin: OriginFor<T>,
+            index: FundIndex,
+            new_goal: BalanceOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            let mut fund = Self::get_fund(index)?;
+            ensure!(fund.creator == who, Error::<T>::NotFundCreator);
+            let now = <frame_system::Module<T>>::block_number();
+            ensure!(fund.end > now, Error::<T>::ContributionPeriodOver);
+            ensure!(fund.raised.is_zero(), Error::<T>::ContributionsExist);
+            ensure!(new_goal < fund.goal, Error::<T>::GoalNotLowered);
+            ensure!(new_goal >= T::MinGoal::get(), Error::<T>::GoalTooLow);
+            ensure!(
+                fund.soft_cap.map_or(true, |cap| cap <= new_goal),
+                Error::<T>::InvalidSoftCap
+            );
+            Self::ensure_valid_stretch_goals(new_goal, &fund.stretch_goals)?;
+
+            fund.goal = new_goal;
+            <Funds<T>>::insert(index, &fund);
+
+            Self::deposit_event(Event::GoalAdjusted(index, new_goal));
+            Ok(().into())
+        }
+
+        /// Redirect a fund's payout to a new beneficiary, e.g. when migrating to a multisig.
+        ///
+        /// Only the creator may do this, and only while the fund is still open and nobody has
+        /// contributed yet. Backers always know who they are paying.
+        #[pallet::weight(10_000)]
+        pub fn set_beneficiary(
+            origin: OriginFor<T>,
+            index: FundIndex,
+            new: AccountIdOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            let mut fund = Self::get_fund(index)?;
+            ensure!(fund.creator == who, Error::<T>::NotFundCreator);
+            let now = <frame_system::Module<T>>::block_number();
+            ensure!(fund.end > now, Error::<T>::ContributionPeriodOver);
+            ensure!(fund.raised.is_zero(), Error::<T>::ContributionsExist);
+            ensure!(
+                new != Self::fund_account_id(index),
+                Error::<T>::InvalidBeneficiary
+            );
+
+            let old = sp_std::mem::replace(&mut fund.beneficiary, new.clone());
+            <Funds<T>>::insert(index, &fund);
+
+            Self::deposit_event(Event::BeneficiaryChanged(index, old, new));
+            Ok(().into())
+        }
+
+        /// Withdraw full balance of a contributor to a fund that ended without succeeding
+        #[pallet::weight(10_000)]
+        pub fn withdraw(origin: OriginFor<T>, index: FundIndex) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            let mut fund = Self::get_fund(index)?;
+            Self::ensure_not_frozen(index)?;
+            let now = <frame_system::Module<T>>::block_number();
+            ensure!(fund.end < now, Error::<T>::FundStillActive);
+            // Once the fund can be dissolved, whatever is left belongs to the dissolver
+            ensure!(
+                now < fund.end + T::RetirementPeriod::get(),
+                Error::<T>::RefundPeriodExpired
+            );
+            // A successful fund may still be waiting to be dispensed
+            ensure!(
+                !Self::is_successful(index, &fund),
+                Error::<T>::SuccessfulFund
+            );
+
+            let balance = Self::contribution_try_get(index, &who)?.unwrap_or_else(Zero::zero);
+            ensure!(balance > Zero::zero(), Error::<T>::NoContribution);
+
+            // Return funds to caller without charging a transfer fee
+            let pot = Self::fund_account_id(index);
+            Self::pay_from_pot(&pot, &who, balance)?;
+
+            // Update storage
+            Self::contribution_kill(index, &who);
+            <ContributorCount<T>>::mutate(index, |count| *count = count.saturating_sub(1));
+            fund.raised = fund.raised.saturating_sub(balance);
+            <Funds<T>>::insert(index, &fund);
+
+            Self::deposit_event(Event::Withdrew(who.clone(), index, balance, now));
+            Self::deposit_event(Event::ContributionRefunded(
+                who,
+                index,
+                balance,
+                RefundReason::Voluntary,
+                now,
+            ));
+
+            Ok(().into())
+        }
+
+        /// Dissolve an entire crowdfund after its retirement period has expired.
+        /// Anyone can call this function, and they are incentivized to do so because
+        /// they inherit the deposit.
+        ///
+        /// Clearing the contributions left in the fund's child tries costs the same per entry as
+        /// a batch of `refund_all` without a limit: a fund holds at most `MaxContributors`, so
+        /// that many are charged up front and the unused weight is refunded.
+        #[pallet::weight(T::WeightInfo::dissolve(T::MaxContributors::get()))]
+        pub fn dissolve(origin: OriginFor<T>, index: FundIndex) -> DispatchResultWithPostInfo {
+            let reporter = ensure_signed(origin)?;
+
+            let mut fund = Self::get_fund(index)?;
+            Self::ensure_not_frozen(index)?;
+
+            // Check that enough time has passed to remove from storage
+            let now = <frame_system::Module<T>>::block_number();
+            ensure!(
+                now >= fund.end + T::RetirementPeriod::get(),
+                Error::<T>::FundNotRetired
+            );
+
+            // The sponsor is not a contributor who could have withdrawn, so their matching
+            // funds are returned rather than dissolved
+            let pot = Self::fund_account_id(index);
+            let failed = !Self::is_successful(index, &fund);
+            Self::settle_match(index, &pot, &mut fund, failed)?;
+            let amount = fund.deposit + fund.raised;
+            let remaining_entries = Self::batch_limit(Some(Self::contributor_count(index)));
+
+            // The deposit and any remaining funds go wherever the runtime decides
+            T::DissolveDestination::handle(
+                &reporter,
+                T::Currency::withdraw(
+                    &pot,
+                    amount,
+                    WithdrawReasons::TRANSFER,
+                    ExistenceRequirement::AllowDeath,
+                )?,
+            );
+
+            Self::remove_fund(index, &fund);
+
+            Self::deposit_event(Event::Dissolved(index, now, reporter, amount));
+
+            Ok(Some(T::WeightInfo::dissolve(remaining_entries)).into())
+        }
+
+        /// Dispense a payment to the beneficiary of a successful crowdfund.
+        /// The beneficiary receives the contributed funds, and the deposit is kept for the
+        /// creator to collect with `claim_deposit`.
+        ///
+        /// Successful funds are also dispensed automatically once they end, see `on_initialize`.
+        #[pallet::weight(10_000)]
+        pub fn dispense(origin: OriginFor<T>, index: FundIndex) -> DispatchResultWithPostInfo {
+            let caller = ensure_signed(origin)?;
+
+            Self::do_dispense(index, &caller)?;
+
+            Ok(().into())
+        }
+
+        /// Take back the deposit of a dispensed fund. Only the creator may do this, once.
+        #[pallet::weight(10_000)]
+        pub fn claim_deposit(origin: OriginFor<T>, index: FundIndex) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            let (creator, deposit) = Self::unclaimed_deposit(index).ok_or(Error::<T>::NoDeposit)?;
+            ensure!(creator == who, Error::<T>::NotFundCreator);
+
+            let account = Self::fund_account_id(index);
+            Self::pay_from_pot(&account, &who, deposit)?;
+            // Only a held payout is owed to anyone else once the fund is dispensed, so the rest of
+            // the pot is emptied and, without one, its account can be reaped
+            let held = Self::held_payout(index).map_or_else(Zero::zero, |payout| payout.remaining);
+            Self::refund_dust_to_creator(index, &account, &who, held)?;
+            <UnclaimedDeposits<T>>::remove(index);
+
+            Self::deposit_event(Event::DepositClaimed(index, who, deposit));
+            Ok(().into())
+        }
+
+        /// Release to the beneficiary whatever part of a held payout has vested since it was
+        /// last released, see `HeldPayout`. Anyone may call this.
+        #[pallet::weight(10_000)]
+        pub fn release_vested(
+            origin: OriginFor<T>,
+            index: FundIndex,
+        ) -> DispatchResultWithPostInfo {
+            ensure_signed(origin)?;
+
+            let mut payout = Self::held_payout(index).ok_or(Error::<T>::NoHeldPayout)?;
+            let now = <frame_system::Module<T>>::block_number();
+            let elapsed = BalanceOf::<T>::from(
+                now.saturating_sub(payout.released_at)
+                    .saturated_into::<u32>(),
+            );
+            let amount = payout
+                .per_block
+                .saturating_mul(elapsed)
+                .min(payout.remaining);
+            ensure!(!amount.is_zero(), Error::<T>::NothingToRelease);
+
+            let pot = Self::fund_account_id(index);
+            Self::pay_from_pot(&pot, &payout.beneficiary, amount)?;
+
+            payout.remaining -= amount;
+            if payout.remaining.is_zero() {
+                <HeldPayouts<T>>::remove(index);
+            } else {
+                payout.released_at = now;
+                <HeldPayouts<T>>::insert(index, &payout);
+            }
+
+            Self::deposit_event(Event::PayoutReleased(index, payout.beneficiary, amount));
+            Ok(().into())
+        }
+
+        /// Refund up to `limit` contributors of a fund that ended without succeeding, until its
+        /// retirement period is over. Anyone can call this to spare contributors from
+        /// withdrawing one by one.
+        ///
+        /// Without a `limit`, up to `MaxContributors` are refunded. The weight of the whole batch
+        /// is charged up front, and whatever was not used is refunded afterwards.
+        #[pallet::weight(T::WeightInfo::refund_all(Pallet::<T>::batch_limit(*limit)))]
+        pub fn refund_all(
+            origin: OriginFor<T>,
+            index: FundIndex,
+            limit: Option<u32>,
+        ) -> DispatchResultWithPostInfo {
+            ensure_signed(origin)?;
+
+            let mut fund = Self::get_fund(index)?;
+            Self::ensure_not_frozen(index)?;
+            let now = <frame_system::Module<T>>::block_number();
+            ensure!(fund.end < now, Error::<T>::FundStillActive);
+            // Once the fund can be dissolved, whatever is left belongs to the dissolver
+            ensure!(
+                now < fund.end + T::RetirementPeriod::get(),
+                Error::<T>::RefundPeriodExpired
+            );
+            ensure!(
+                !Self::is_successful(index, &fund),
+                Error::<T>::SuccessfulFund
+            );
+
+            let account = Self::fund_account_id(index);
+            let mut refunded = 0u32;
+            for (who, balance) in Self::try_contributions(index, Self::batch_limit(limit))? {
+                Self::pay_from_pot(&account, &who, balance)?;
+                Self::contribution_kill(index, &who);
+                <ContributorCount<T>>::mutate(index, |count| *count = count.saturating_sub(1));
+                fund.raised = fund.raised.saturating_sub(balance);
+                refunded += 1;
+                Self::deposit_event(Event::ContributionRefunded(
+                    who,
+                    index,
+                    balance,
+                    RefundReason::Sweep,
+                    now,
+                ));
+            }
+            <Funds<T>>::insert(index, &fund);
+
+            let more_remaining = !Self::contributions(index, 1).is_empty();
+            Self::deposit_event(Event::BatchRefunded(index, refunded, more_remaining));
+
+            Ok(Some(T::WeightInfo::refund_all(refunded)).into())
+        }
+
+        /// Shut down an abusive fund. Every contributor is refunded, the creator's deposit and the
+        /// contribution fees are seized and handed to `OnSlash`, and the fund is removed from
+        /// storage. Private contributions are left in the pot, to be claimed with
+        /// `withdraw_private`. A fund past its retirement period can only be dissolved.
+        #[pallet::weight(T::WeightInfo::report_and_slash(T::MaxContributors::get()))]
+        pub fn report_and_slash(
+            origin: OriginFor<T>,
+            index: FundIndex,
+        ) -> DispatchResultWithPostInfo {
+            T::AdminOrigin::ensure_origin(origin)?;
+
+            let mut fund = Self::get_fund(index)?;
+            let now = <frame_system::Module<T>>::block_number();
+            // Once retired, whatever is left belongs to whoever dissolves the fund
+            ensure!(
+                now < fund.end + T::RetirementPeriod::get(),
+                Error::<T>::RefundPeriodExpired
+            );
+            let account = Self::fund_account_id(index);
+
+            // Contributors get their money back. Their number is bounded by `MaxContributors`.
+            // They are read before anything is written, so a corrupt entry changes nothing.
+            let contributions = Self::try_contributions(index, T::MaxContributors::get())?;
+
+            // A sponsor gets their matching funds back, like any other contributor
+            Self::settle_match(index, &account, &mut fund, true)?;
+
+            let mut refunded: BalanceOf<T> = Zero::zero();
+            for (who, balance) in contributions {
+                Self::pay_from_pot(&account, &who, balance)?;
+                refunded += balance;
+                Self::deposit_event(Event::ContributionRefunded(
+                    who,
+                    index,
+                    balance,
+                    RefundReason::Slash,
+                    now,
+                ));
+            }
+
+            // Private contributions cannot be refunded to a known account, so they stay in the
+            // pot until claimed. Only the deposit and the fees, which no contributor is owed, are
+            // seized.
+            let unclaimed = fund
+                .raised
+                .saturating_sub(refunded)
+                .saturating_sub(fund.fees);
+            let seized = fund.deposit + fund.fees;
+            T::OnSlash::on_unbalanced(T::Currency::withdraw(
+                &account,
+                seized,
+                WithdrawReasons::TRANSFER,
+                ExistenceRequirement::AllowDeath,
+            )?);
+
+            if !unclaimed.is_zero() {
+                <UnclaimedPrivate<T>>::insert(index, unclaimed);
+            }
+            Self::remove_fund(index, &fund);
+
+            Self::deposit_event(Event::Slashed(index, now, seized));
+
+            Ok(().into())
+        }
+
+        /// Freeze or unfreeze a fund. While frozen, nothing can be paid out of it: `withdraw`,
+        /// `withdraw_private`, `refund_all`, `release_match`, `dispense` and `dissolve` fail, and
+        /// automatic dispensing waits until the fund is unfrozen. Contributions are accepted only if
+        /// `AllowContributionsWhileFrozen` is set. `report_and_slash` still applies.
+        #[pallet::weight(10_000)]
+        pub fn set_fund_frozen(
+            origin: OriginFor<T>,
+            index: FundIndex,
+            frozen: bool,
+        ) -> DispatchResultWithPostInfo {
+            T::AdminOrigin::ensure_origin(origin)?;
+
+            Self::get_fund(index)?;
+            if frozen {
+                <Frozen<T>>::insert(index, true);
+            } else {
+                <Frozen<T>>::remove(index);
+            }
+
+            Self::deposit_event(Event::FrozenChanged(index, frozen));
+
+            Ok(().into())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// The fraction of a fund's goal that has been raised so far, capped at 100%.
+        ///
+        /// A fund with a zero goal counts as fully funded. Returns `None` if the fund does not exist.
+        pub fn goal_progress(index: FundIndex) -> Option<Perbill> {
+            let fund = Self::funds(index)?;
+            if fund.goal.is_zero() {
+                return Some(Perbill::one());
+            }
+            Some(Perbill::from_rational_approximation(
+                fund.raised.min(fund.goal),
+                fund.goal,
+            ))
+        }
+
+        /// How many blocks until a fund stops accepting contributions, zero once it has.
+        /// Returns `None` if the fund does not exist.
+        pub fn blocks_until_end(index: FundIndex) -> Option<T::BlockNumber> {
+            let fund = Self::funds(index)?;
+            let now = <frame_system::Module<T>>::block_number();
+            Some(fund.end.saturating_sub(now))
+        }
+
+        /// How many blocks until a fund can be dissolved and its contributions can no longer be
+        /// withdrawn, zero once it can. Returns `None` if the fund does not exist.
+        pub fn blocks_until_dissolvable(index: FundIndex) -> Option<T::BlockNumber> {
+            let fund = Self::funds(index)?;
+            let now = <frame_system::Module<T>>::block_number();
+            Some((fund.end + T::RetirementPeriod::get()).saturating_sub(now))
+        }
+
+        /// The total raised by a fund, or zero if it does not exist.
+        ///
+        /// Always equal to `funds(index).raised`, but only decodes the fields of `FundInfo` up to
+        /// and including `raised` instead of the whole struct. Relies on the field order of
+        /// `FundInfo`.
+        pub fn raised(index: FundIndex) -> BalanceOf<T> {
+            unhashed::get_raw(&<Funds<T>>::hashed_key_for(index))
+                .and_then(|raw| {
+                    <(AccountIdOf<T>, AccountIdOf<T>, BalanceOf<T>, BalanceOf<T>)>::decode(
+                        &mut &raw[..],
+                    )
+                    .ok()
+                })
+                .map_or_else(Zero::zero, |(_creator, _beneficiary, _deposit, raised)| {
+                    raised
+                })
+        }
+
+        /// Pay out a successful fund that has ended and remove it from storage, leaving the
+        /// deposit for the creator to claim. `caller` is reported as the dispenser.
+        ///
+        /// All checks happen before any funds move, and a payout that still fails partway is
+        /// rolled back as a whole.
+        #[transactional]
+        fn do_dispense(index: FundIndex, caller: &T::AccountId) -> DispatchResult {
+            let mut fund = Self::get_fund(index)?;
+            Self::ensure_not_frozen(index)?;
+
+            // Check that enough time has passed to remove from storage
+            let now = <frame_system::Module<T>>::block_number();
+            ensure!(now >= fund.end, Error::<T>::FundStillActive);
+
+            // Check that the fund was actually successful
+            ensure!(
+                Self::is_successful(index, &fund),
+                Error::<T>::UnsuccessfulFund
+            );
+
+            // Only one vesting schedule is supported per account. Rather than leave the fund
+            // stuck, the payout of a beneficiary that already vests is held in the pot.
+            let hold = fund.vesting_blocks.is_some()
+                && T::VestingSchedule::vesting_balance(&fund.beneficiary).is_some();
+
+            let account = Self::fund_account_id(index);
+
+            // The sponsor keeps whatever was not drawn
+            Self::settle_match(index, &account, &mut fund, false)?;
+
+            // Secondary beneficiaries collect their share, rounded down
+            let mut remaining = fund.raised;
+            for (who, share) in fund.splits.iter() {
+                let cut = share.mul_floor(fund.raised);
+                remaining = remaining.saturating_sub(cut);
+                Self::pay_from_pot(&account, who, cut)?;
+            }
+
+            // Beneficiary collects the rest of the contributed funds, including any rounding remainder
+            if !hold {
+                Self::pay_from_pot(&account, &fund.beneficiary, remaining)?;
+            }
+
+            // Lock the beneficiary's payout so it is released linearly over the vesting period
+            let mut held = Zero::zero();
+            if let Some(blocks) = fund.vesting_blocks {
+                let blocks = BalanceOf::<T>::from(blocks.saturated_into::<u32>().max(1));
+                let mut per_block = remaining / blocks;
+                if !(remaining % blocks).is_zero() {
+                    per_block += One::one();
+                }
+                if hold {
+                    held = remaining;
+                    <HeldPayouts<T>>::insert(
+                        index,
+                        HeldPayout {
+                            beneficiary: fund.beneficiary.clone(),
+                            remaining,
+                            per_block,
+                            released_at: now,
+                        },
+                    );
+                } else {
+                    T::VestingSchedule::add_vesting_schedule(
+                        &fund.beneficiary,
+                        remaining,
+                        per_block,
+                        now,
+                    )?;
+                }
+            }
+
+            // The deposit and any held payout stay in the pot until they are claimed. Anything
+            // else still in the pot is dust that would otherwise linger there forever.
+            Self::refund_dust_to_creator(index, &account, &fund.creator, fund.deposit + held)?;
+            <UnclaimedDeposits<T>>::insert(index, (fund.creator.clone(), fund.deposit));
+
+            Self::remove_fund(index, &fund);
+
+            if hold {
+                Self::deposit_event(Event::PayoutHeld(index, fund.beneficiary.clone(), held));
+            }
+            Self::deposit_event(Event::Dispensed(index, now, caller.clone(), fund.raised));
+            T::OnSuccess::on_success(&fund.beneficiary, fund.raised);
+
+            Ok(())
+        }
+
+        /// The creation logic shared by `create`, `create_with_duration` and
+        /// `create_and_contribute`. Returns the new fund's index.
+        fn do_create(
+            creator: T::AccountId,
+            beneficiary: AccountIdOf<T>,
+            goal: BalanceOf<T>,
+            end: T::BlockNumber,
+            params: FundParamsOf<T>,
+        ) -> Result<FundIndex, DispatchError> {
+            let FundParams {
+                splits,
+                vesting_blocks,
+                soft_cap,
+                stretch_goals,
+                min_contributors,
+                category,
+                min_contribution,
+                emit_contributions,
+            } = params;
+            let now = <frame_system::Module<T>>::block_number();
+            if let Some(last) = Self::last_created(&creator) {
+                ensure!(
+                    now >= last + T::CreationCooldown::get(),
+                    Error::<T>::CreationTooSoon
+                );
+            }
+            Self::check_create_params(goal, end)?;
+            Self::ensure_valid_splits(&splits)?;
+            ensure!(
+                vesting_blocks.map_or(true, |blocks| !blocks.is_zero()),
+                Error::<T>::InvalidVestingPeriod
+            );
+            ensure!(
+                soft_cap.map_or(true, |cap| cap <= goal),
+                Error::<T>::InvalidSoftCap
+            );
+            Self::ensure_valid_stretch_goals(goal, &stretch_goals)?;
+            ensure!(
+                min_contribution.map_or(true, |min| min >= T::MinContribution::get()),
+                Error::<T>::FundMinimumTooLow
+            );
+            // The pot account is derived from the index the fund is about to get. Paying the
+            // pot out to itself would leave the funds stranded once the fund is removed.
+            let index = <FundCount<T>>::get();
+            let account = Self::fund_account_id(index);
+            ensure!(beneficiary != account, Error::<T>::InvalidBeneficiary);
+            let deposit = T::DepositCalculator::deposit_for(goal);
+
+            let imb = T::Currency::withdraw(
+                &creator,
+                deposit,
+                WithdrawReasons::TRANSFER,
+                ExistenceRequirement::AllowDeath,
+            )?;
+
+            // not protected against overflow, see safemath section
+            <FundCount<T>>::put(index + 1);
+            // No fees are paid here if we need to create this account; that's why we don't just
+            // use the stock `transfer`.
+            T::Currency::resolve_creating(&account, imb);
+
+            <Funds<T>>::insert(
+                index,
+                FundInfo {
+                    creator: creator.clone(),
+                    beneficiary,
+                    deposit,
+                    raised: Zero::zero(),
+                    fees: Zero::zero(),
+                    created: now,
+                    end,
+                    goal,
+                    soft_cap,
+                    stretch_goals,
+                    stretch_reached: 0,
+                    min_contributors,
+                    splits,
+                    vesting_blocks,
+                    category,
+                    min_contribution,
+                    emit_contributions,
+                },
+            );
+            <FundsByCategory<T>>::insert(category, index, ());
+            <LastCreated<T>>::insert(&creator, now);
+            <EndingAt<T>>::append(end, index);
+
+            Self::deposit_event(Event::Created(index, now));
+            Ok(index)
+        }
+
+        /// The contribution logic shared by `contribute`, `contribute_private` and
+        /// `create_and_contribute`. A contribution with a `commitment` is recorded privately
+        /// under it rather than under `who`.
+        #[transactional]
+        fn do_contribute(
+            who: T::AccountId,
+            index: FundIndex,
+            value: BalanceOf<T>,
+            client_ref: Option<u64>,
+            commitment: Option<T::Hash>,
+        ) -> DispatchResultWithPostInfo {
+            ensure!(
+                !value.is_zero(),
+                Error::<T>::ZeroContribution.with_weight(0)
+            );
+            ensure!(
+                value >= T::MinContribution::get(),
+                Error::<T>::ContributionTooSmall.with_weight(0)
+            );
+            let mut fund =
+                Self::get_fund(index).map_err(|e| e.with_weight(T::DbWeight::get().reads(1)))?;
+            Self::ensure_open_to_contributions(index)?;
+            Self::ensure_fund_minimum(&fund, value)?;
+
+            // Make sure crowdfund has not ended
+            let now = <frame_system::Module<T>>::block_number();
+            ensure!(fund.end > now, Error::<T>::ContributionPeriodOver);
+
+            let balance = match &commitment {
+                Some(commitment) => Self::private_contribution_try_get(index, commitment)?
+                    .unwrap_or_else(Zero::zero),
+                None => Self::contribution_get(index, &who),
+            };
+            let is_new = balance.is_zero();
+            ensure!(
+                !is_new || Self::contributor_count(index) < T::MaxContributors::get(),
+                Error::<T>::TooManyContributors
+            );
+            if let Some(client_ref) = client_ref {
+                ensure!(
+                    !<ContributionRefs<T>>::contains_key(index, (who.clone(), client_ref)),
+                    Error::<T>::DuplicateContribution
+                );
+            }
+
+            // The whole value counts towards the fund, but only what is left after the fee is
+            // recorded as the contributor's refundable balance. The fee is never more than the
+            // value, so `fees` cannot overflow if `raised` does not.
+            let fee = T::ContributionFee::get().mul_floor(value);
+            let mut pool = Self::match_pool(index);
+            let matched = pool.as_ref().map_or(Zero::zero(), |pool| {
+                pool.ratio.mul_floor(value).min(pool.remaining)
+            });
+            let mut raised = fund
+                .raised
+                .checked_add(&value)
+                .and_then(|raised| raised.checked_add(&matched))
+                .ok_or(Error::<T>::Overflow)?;
+            let balance = balance
+                .checked_add(&(value - fee))
+                .ok_or(Error::<T>::Overflow)?;
+
+            // Add contribution to the fund. The transfer happens before anything is written: if it
+            // fails, the fund and the child trie are left untouched. If drawing the match fails
+            // after it, the transfer is undone along with everything else.
+            let pot = Self::fund_account_id(index);
+            T::Currency::transfer(&who, &pot, value, Self::contributor_existence())?;
+
+            // The matched amount moves from the sponsor's reserve into the pot. Anything the
+            // reserve could not cover, e.g. because it was slashed elsewhere, is not counted.
+            let mut drawn = Zero::zero();
+            if let Some(pool) = pool.as_mut().filter(|_| !matched.is_zero()) {
+                let missing = T::Currency::repatriate_reserved(
+                    &pool.sponsor,
+                    &pot,
+                    matched,
+                    BalanceStatus::Free,
+                )?;
+                raised = raised.saturating_sub(missing);
+                drawn = matched - missing;
+                pool.remaining = pool.remaining.saturating_sub(matched);
+                pool.drawn = pool.drawn.saturating_add(drawn);
+                <Matches<T>>::insert(index, &*pool);
+            }
+
+            fund.raised = raised;
+            fund.fees += fee;
+            let reached = Self::advance_stretch_goals(&mut fund);
+            Funds::<T>::insert(index, &fund);
+
+            match &commitment {
+                Some(commitment) => Self::private_contribution_put(index, commitment, &balance),
+                None => {
+                    Self::contribution_put(index, &who, &balance);
+                    Self::contribution_detail_put(index, &who, now, balance);
+                }
+            }
+            if is_new {
+                <ContributorCount<T>>::mutate(index, |count| *count += 1);
+            }
+            if let Some(client_ref) = client_ref {
+                <ContributionRefs<T>>::insert(index, (who.clone(), client_ref), ());
+            }
+
+            if fund.emit_contributions {
+                Self::deposit_event(match commitment {
+                    Some(commitment) => {
+                        Event::ContributedPrivately(index, commitment, balance, now)
+                    }
+                    None => Event::Contributed(who.clone(), index, balance, now, is_new),
+                });
+                if !drawn.is_zero() {
+                    Self::deposit_event(Event::Matched(index, who, drawn));
+                }
+            } else {
+                Self::defer_to_summary(index);
+            }
+            for stretch in reached {
+                Self::deposit_event(Event::StretchReached(index, stretch, now));
+            }
+
+            Ok(().into())
+        }
+
+        /// Check `goal` and `end` the way `create` would against the current chain state,
+        /// without creating anything.
+        ///
+        /// Lets wallets reject a bad `create` before it is signed and its fee paid.
+        pub fn validate_create(goal: BalanceOf<T>, end: T::BlockNumber) -> DispatchResult {
+            Self::check_create_params(goal, end)
+        }
+
+        /// The preconditions on a new fund's goal and end shared by `create` and
+        /// `validate_create`.
+        fn check_create_params(goal: BalanceOf<T>, end: T::BlockNumber) -> DispatchResult {
+            let now = <frame_system::Module<T>>::block_number();
+            ensure!(end > now, Error::<T>::EndTooEarly);
+            ensure!(end - now <= T::MaxDuration::get(), Error::<T>::EndTooFar);
+            ensure!(goal >= T::MinGoal::get(), Error::<T>::GoalTooLow);
+            ensure!(
+                T::DepositCalculator::deposit_for(goal) >= T::Currency::minimum_balance(),
+                Error::<T>::DepositTooLow
+            );
+            Ok(())
+        }
+
+        /// Close a fund's match, if it has one: unreserve what the sponsor has left and, if
+        /// `return_drawn`, pay back what was already drawn into the pot as well, taking it out of
+        /// the amount raised.
+        fn settle_match(
+            index: FundIndex,
+            pot: &T::AccountId,
+            fund: &mut FundInfoOf<T>,
+            return_drawn: bool,
+        ) -> DispatchResult {
+            let pool = match <Matches<T>>::get(index) {
+                Some(pool) => pool,
+                None => return Ok(()),
+            };
+            let mut released =
+                pool.remaining - T::Currency::unreserve(&pool.sponsor, pool.remaining);
+            if return_drawn {
+                let drawn = pool.drawn.min(fund.raised);
+                Self::pay_from_pot(pot, &pool.sponsor, drawn)?;
+                fund.raised -= drawn;
+                released += drawn;
+            }
+            <Matches<T>>::remove(index);
+            Self::deposit_event(Event::MatchReleased(index, pool.sponsor, released));
+            Ok(())
+        }
+
+        /// Pay out a private contribution to a fund that was slashed. Once the last one is
+        /// claimed, the fund's private contributions child trie is removed too.
+        fn claim_slashed_private(
+            who: T::AccountId,
+            index: FundIndex,
+            secret: Vec<u8>,
+            unclaimed: BalanceOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let commitment = T::Hashing::hash_of(&(&who, &secret));
+            let balance = Self::private_contribution_try_get(index, &commitment)?
+                .ok_or(Error::<T>::NoContribution)?;
+
+            let pot = Self::fund_account_id(index);
+            Self::pay_from_pot(&pot, &who, balance)?;
+
+            Self::private_contribution_kill(index, &commitment);
+            let unclaimed = unclaimed.saturating_sub(balance);
+            if unclaimed.is_zero() {
+                <UnclaimedPrivate<T>>::remove(index);
+                child::kill_storage(&Self::private_id_from_index(index), None);
+            } else {
+                <UnclaimedPrivate<T>>::insert(index, unclaimed);
+            }
+
+            let now = <frame_system::Module<T>>::block_number();
+            Self::deposit_event(Event::ContributionRefunded(
+                who,
+                index,
+                balance,
+                RefundReason::Slash,
+                now,
+            ));
+
+            Ok(().into())
+        }
+
+        /// Check a contribution against the fund's own minimum, if it has one. The global
+        /// `MinContribution` is checked separately, before the fund is read.
+        fn ensure_fund_minimum(fund: &FundInfoOf<T>, value: BalanceOf<T>) -> DispatchResult {
+            ensure!(
+                fund.min_contribution.map_or(true, |min| value >= min),
+                Error::<T>::ContributionBelowFundMinimum
+            );
+            Ok(())
+        }
+
+        /// Note that a fund received a contribution without emitting an event for it, so that
+        /// `on_finalize` emits its summary.
+        fn defer_to_summary(index: FundIndex) {
+            <PendingSummaries<T>>::mutate(|pending| {
+                if !pending.contains(&index) {
+                    pending.push(index);
+                }
+            });
+        }
+
+        /// Fail if the fund is frozen, see `set_fund_frozen`.
+        fn ensure_not_frozen(index: FundIndex) -> DispatchResult {
+            ensure!(!Self::is_frozen(index), Error::<T>::FundFrozen);
+            Ok(())
+        }
+
+        /// Fail if the fund is frozen and the runtime does not allow contributions to frozen
+        /// funds.
+        fn ensure_open_to_contributions(index: FundIndex) -> DispatchResult {
+            if T::AllowContributionsWhileFrozen::get() {
+                return Ok(());
+            }
+            Self::ensure_not_frozen(index)
+        }
+
+        /// Whether a contribution may reap the contributor's account, see
+        /// `AllowContributorDeath`.
+        fn contributor_existence() -> ExistenceRequirement {
+            if T::AllowContributorDeath::get() {
+                ExistenceRequirement::AllowDeath
+            } else {
+                ExistenceRequirement::KeepAlive
+            }
+        }
+
+        /// Count every stretch goal `fund.raised` has now reached, returning the positions of
+        /// those newly reached.
+        fn advance_stretch_goals(fund: &mut FundInfoOf<T>) -> sp_std::ops::Range<u32> {
+            let first_unreached = fund.stretch_reached;
+            while let Some(target) = fund.stretch_goals.get(fund.stretch_reached as usize) {
+                if fund.raised < *target {
+                    break;
+                }
+                fund.stretch_reached += 1;
+            }
+            first_unreached..fund.stretch_reached
+        }
+
+        /// The number of contributors a batch processes: `limit`, or `MaxContributors` without
+        /// one, never more than `MaxContributors`.
+        fn batch_limit(limit: Option<u32>) -> u32 {
+            let max = T::MaxContributors::get();
+            limit.unwrap_or(max).min(max)
+        }
+
+        /// The largest number of contributors `refund_all` can refund within `remaining` weight,
+        /// capped at `MaxContributors`. Exposed through the runtime API, so that callers can pick
+        /// a `limit` that fits in a block.
+        pub fn refund_limit(remaining: Weight) -> u32 {
+            let base = T::WeightInfo::refund_all(0);
+            let per_entry = T::WeightInfo::refund_all(1).saturating_sub(base);
+            let max = T::MaxContributors::get();
+            if per_entry.is_zero() {
+                return max;
+            }
+            (remaining.saturating_sub(base) / per_entry).min(max as Weight) as u32
+        }
+
+        /// The funds in a category that have not been dispensed, dissolved or slashed, in no
+        /// particular order.
+        pub fn funds_in_category(category: Category) -> Vec<FundIndex> {
+            <FundsByCategory<T>>::iter_prefix(category)
+                .map(|(index, ())| index)
+                .collect()
+        }
+
+        /// Page through the funds that have not been dispensed or dissolved, in index order.
+        ///
+        /// Starts at `start` and returns up to `limit` funds, along with the index to pass as
+        /// `start` for the next page, or `None` once every fund has been visited. At most
+        /// `limit * ACTIVE_FUNDS_SCAN_FACTOR` indices are read, so a page can come back short,
+        /// or even empty, while there are still funds to visit.
+        pub fn active_funds(
+            start: FundIndex,
+            limit: u32,
+        ) -> (Vec<(FundIndex, FundInfoOf<T>)>, Option<FundIndex>) {
+            let count = Self::fund_count();
+            let end = start
+                .saturating_add(limit.saturating_mul(ACTIVE_FUNDS_SCAN_FACTOR))
+                .min(count);
+            let mut funds = Vec::new();
+            let mut index = start;
+            while index < end && (funds.len() as u32) < limit {
+                if let Some(fund) = Self::funds(index) {
+                    funds.push((index, fund));
+                }
+                index += 1;
+            }
+            let next = if index < count { Some(index) } else { None };
+            (funds, next)
+        }
+
+        /// Whether a fund has raised enough to be dispensed: its soft cap if it has one,
+        /// otherwise its goal. If the fund requires a minimum number of contributors, that
+        /// must be met as well.
+        pub fn is_successful(index: FundIndex, fund: &FundInfoOf<T>) -> bool {
+            fund.raised >= fund.soft_cap.unwrap_or(fund.goal)
+                && fund
+                    .min_contributors
+                    .map_or(true, |min| Self::contributor_count(index) >= min)
+        }
+
+        /// The info of an existing fund. Every call looks funds up through this, so a missing
+        /// fund is always reported as `InvalidIndex`.
+        fn get_fund(index: FundIndex) -> Result<FundInfoOf<T>, DispatchError> {
+            Self::funds(index).ok_or_else(|| Error::<T>::InvalidIndex.into())
+        }
+
+        /// The account ID of the fund pot.
+        ///
+        /// This actually does computation. If you need to keep using it, then make sure you cache the
+        /// value and only call this once.
+        pub fn fund_account_id(index: FundIndex) -> T::AccountId {
+            let res = PALLET_ID.into_sub_account(index);
+            debug::info!("fund_account_id: {:?}", &res);
+            res
+        }
+
+        /// Pay `amount` out of a fund's pot into `to`, creating `to` if needed.
+        ///
+        /// Takes the pot account rather than the fund index, so that callers paying out several
+        /// times derive it once with `fund_account_id` and pass it along.
+        fn pay_from_pot(
+            pot: &T::AccountId,
+            to: &T::AccountId,
+            amount: BalanceOf<T>,
+        ) -> DispatchResult {
+            let _ = T::Currency::resolve_creating(
+                to,
+                T::Currency::withdraw(
+                    pot,
+                    amount,
+                    WithdrawReasons::TRANSFER,
+                    ExistenceRequirement::AllowDeath,
+                )?,
+            );
+            Ok(())
+        }
+
+        /// Pay whatever the pot holds beyond `keep` to the fund's creator, e.g. rounding
+        /// remainders or transfers made straight to the pot.
+        fn refund_dust_to_creator(
+            index: FundIndex,
+            pot: &T::AccountId,
+            creator: &T::AccountId,
+            keep: BalanceOf<T>,
+        ) -> DispatchResult {
+            let dust = T::Currency::free_balance(pot).saturating_sub(keep);
+            if !dust.is_zero() {
+                Self::pay_from_pot(pot, creator, dust)?;
+                Self::deposit_event(Event::DustSwept(index, creator.clone(), dust));
+            }
+            Ok(())
+        }
+
+        /// Check that a payout split fits within the configured bound and does not hand out
+        /// more than the whole of the raised amount.
+        fn ensure_valid_splits(splits: &[(AccountIdOf<T>, Perbill)]) -> DispatchResult {
+            ensure!(
+                splits.len() as u32 <= T::MaxSplits::get(),
+                Error::<T>::TooManySplits
+            );
+            let total = splits
+                .iter()
+                .fold(0u64, |acc, (_, share)| acc + share.deconstruct() as u64);
+            ensure!(
+                total <= Perbill::one().deconstruct() as u64,
+                Error::<T>::InvalidSplit
+            );
+            Ok(())
+        }
+
+        /// Check that stretch goals fit within the configured bound, and are strictly ascending
+        /// and above the goal.
+        fn ensure_valid_stretch_goals(
+            goal: BalanceOf<T>,
+            stretch_goals: &[BalanceOf<T>],
+        ) -> DispatchResult {
+            ensure!(
+                stretch_goals.len() as u32 <= T::MaxStretchGoals::get(),
+                Error::<T>::TooManyStretchGoals
+            );
+            let mut previous = goal;
+            for target in stretch_goals {
+                ensure!(*target > previous, Error::<T>::InvalidStretchGoals);
+                previous = *target;
+            }
+            Ok(())
+        }
+
+        /// Find the ID associated with the fund
+        ///
+        /// Each fund stores information about its contributors and their contributions in a child trie
+        /// This helper function calculates the id of the associated child trie.
+        pub fn id_from_index(index: FundIndex) -> child::ChildInfo {
+            Self::child_info(b"crowdfnd", index)
+        }
+
+        /// Whether `info` is the contributions child trie of fund `index`, see `id_from_index`.
+        pub fn verify_child_info(index: FundIndex, info: &child::ChildInfo) -> bool {
+            info.storage_key() == Self::id_from_index(index).storage_key()
+        }
+
+        /// The prefixed storage key of a fund's contributions child trie, as taken by the child
+        /// state RPCs.
+        pub fn child_trie_key(index: FundIndex) -> Vec<u8> {
+            Self::id_from_index(index)
+                .prefixed_storage_key()
+                .into_inner()
+        }
+
+        /// Find the ID of the child trie holding `(first contribution block, total)` records
+        /// for each contributor to the fund.
+        pub fn detail_id_from_index(index: FundIndex) -> child::ChildInfo {
+            Self::child_info(b"crowddtl", index)
+        }
+
+        /// Find the unique child trie holding a fund's private contributions, keyed by
+        /// commitment.
+        pub fn private_id_from_index(index: FundIndex) -> child::ChildInfo {
+            Self::child_info(b"crowdprv", index)
+        }
+
+        fn child_info(prefix: &[u8], index: FundIndex) -> child::ChildInfo {
+            let mut buf = Vec::new();
+            buf.extend_from_slice(prefix);
+            buf.extend_from_slice(&index.to_le_bytes()[..]);
+
+            child::ChildInfo::new_default(T::Hashing::hash(&buf).as_ref())
+        }
+
+        /// Record a contribution in the associated child trie.
+        pub fn contribution_put(index: FundIndex, who: &T::AccountId, balance: &BalanceOf<T>) {
+            let id = Self::id_from_index(index);
+            who.using_encoded(|b| child::put(&id, b, &balance));
+        }
+
+        /// Lookup a contribution in the associated child trie. An entry that cannot be decoded
+        /// reads as zero, so use `contribution_try_get` wherever that would lose funds.
+        pub fn contribution_get(index: FundIndex, who: &T::AccountId) -> BalanceOf<T> {
+            let id = Self::id_from_index(index);
+            who.using_encoded(|b| child::get_or_default::<BalanceOf<T>>(&id, b))
+        }
+
+        /// Lookup a contribution in the associated child trie, telling a missing entry (`None`)
+        /// apart from one that cannot be decoded (`CorruptContribution`).
+        pub fn contribution_try_get(
+            index: FundIndex,
+            who: &T::AccountId,
+        ) -> Result<Option<BalanceOf<T>>, DispatchError> {
+            let id = Self::id_from_index(index);
+            match who.using_encoded(|b| child::get_raw(&id, b)) {
+                Some(raw) => BalanceOf::<T>::decode(&mut &raw[..])
+                    .map(Some)
+                    .map_err(|_| Error::<T>::CorruptContribution.into()),
+                None => Ok(None),
+            }
+        }
+
+        /// Record a private contribution under its commitment.
+        fn private_contribution_put(
+            index: FundIndex,
+            commitment: &T::Hash,
+            balance: &BalanceOf<T>,
+        ) {
+            let id = Self::private_id_from_index(index);
+            commitment.using_encoded(|b| child::put(&id, b, &balance));
+        }
+
+        /// Lookup the private contribution recorded under a commitment. An entry that cannot be
+        /// decoded reads as zero, so use `private_contribution_try_get` wherever that would lose
+        /// funds.
+        pub fn private_contribution_get(index: FundIndex, commitment: &T::Hash) -> BalanceOf<T> {
+            let id = Self::private_id_from_index(index);
+            commitment.using_encoded(|b| child::get_or_default::<BalanceOf<T>>(&id, b))
+        }
+
+        /// Lookup the private contribution recorded under a commitment, telling a missing entry
+        /// (`None`) apart from one that cannot be decoded (`CorruptContribution`).
+        pub fn private_contribution_try_get(
+            index: FundIndex,
+            commitment: &T::Hash,
+        ) -> Result<Option<BalanceOf<T>>, DispatchError> {
+            let id = Self::private_id_from_index(index);
+            match commitment.using_encoded(|b| child::get_raw(&id, b)) {
+                Some(raw) => BalanceOf::<T>::decode(&mut &raw[..])
+                    .map(Some)
+                    .map_err(|_| Error::<T>::CorruptContribution.into()),
+                None => Ok(None),
+            }
+        }
+
+        /// Remove the private contribution recorded under a commitment.
+        fn private_contribution_kill(index: FundIndex, commitment: &T::Hash) {
+            let id = Self::private_id_from_index(index);
+            commitment.using_encoded(|b| child::kill(&id, b));
+        }
+
+        /// Record when an account first contributed and its running total. The first
+        /// contribution block is kept from any earlier record.
+        fn contribution_detail_put(
+            index: FundIndex,
+            who: &T::AccountId,
+            now: T::BlockNumber,
+            balance: BalanceOf<T>,
+        ) {
+            let first = Self::contribution_detail(index, who).map_or(now, |(first, _)| first);
+            let id = Self::detail_id_from_index(index);
+            who.using_encoded(|b| child::put(&id, b, &(first, balance)));
+        }
+
+        /// The block at which an account first contributed to a fund, and its total contribution.
+        ///
+        /// Lets matching algorithms weight early backers.
+        pub fn contribution_detail(
+            index: FundIndex,
+            who: &T::AccountId,
+        ) -> Option<(T::BlockNumber, BalanceOf<T>)> {
+            let id = Self::detail_id_from_index(index);
+            who.using_encoded(|b| child::get(&id, b))
+        }
+
+        /// Whether an account has a contribution recorded in the associated child trie.
+        ///
+        /// Only checks for the key, so the balance is never decoded.
+        pub fn has_contributed(index: FundIndex, who: &T::AccountId) -> bool {
+            let id = Self::id_from_index(index);
+            who.using_encoded(|b| child::exists(&id, b))
+        }
+
+        /// Read up to `limit` contributions from the associated child trie, in key order.
+        pub fn contributions(index: FundIndex, limit: u32) -> Vec<(T::AccountId, BalanceOf<T>)> {
+            Self::contributors(index, limit)
+                .into_iter()
+                .map(|who| {
+                    let balance = Self::contribution_get(index, &who);
+                    (who, balance)
+                })
+                .collect()
+        }
+
+        /// Like `contributions`, but fails on an entry that cannot be decoded instead of
+        /// reading it as zero. Used wherever contributions are refunded.
+        pub fn try_contributions(
+            index: FundIndex,
+            limit: u32,
+        ) -> Result<Vec<(T::AccountId, BalanceOf<T>)>, DispatchError> {
+            Self::contributors(index, limit)
+                .into_iter()
+                .map(|who| {
+                    let balance =
+                        Self::contribution_try_get(index, &who)?.unwrap_or_else(Zero::zero);
+                    Ok((who, balance))
+                })
+                .collect()
+        }
+
+        /// Read up to `limit` contributors from the associated child trie, in key order.
+        fn contributors(index: FundIndex, limit: u32) -> Vec<T::AccountId> {
+            let id = Self::id_from_index(index);
+            let mut contributors = Vec::new();
+            let mut key = Vec::new();
+            while (contributors.len() as u32) < limit {
+                key = match sp_io::default_child_storage::next_key(id.storage_key(), &key) {
+                    Some(next) => next,
+                    None => break,
+                };
+                if let Ok(who) = T::AccountId::decode(&mut &key[..]) {
+                    contributors.push(who);
+                }
+            }
+            contributors
+        }
+
+        /// Remove a contribution, along with its detail record, from the associated child tries.
+        pub fn contribution_kill(index: FundIndex, who: &T::AccountId) {
+            let id = Self::id_from_index(index);
+            who.using_encoded(|b| child::kill(&id, b));
+            let id = Self::detail_id_from_index(index);
+            who.using_encoded(|b| child::kill(&id, b));
+        }
+
+        /// Remove a fund and everything recorded about it: its info, its contributor count and
+        /// client references, its category and freeze entries, and its child tries. The
+        /// creator's cooldown record goes too once it no longer restricts them.
+        fn remove_fund(index: FundIndex, fund: &FundInfoOf<T>) {
+            <Funds<T>>::remove(index);
+            <ContributorCount<T>>::remove(index);
+            <ContributionRefs<T>>::remove_prefix(index);
+            <FundsByCategory<T>>::remove(fund.category, index);
+            <Frozen<T>>::remove(index);
+            if let Some(last) = Self::last_created(&fund.creator) {
+                let now = <frame_system::Module<T>>::block_number();
+                if now >= last + T::CreationCooldown::get() {
+                    <LastCreated<T>>::remove(&fund.creator);
+                }
+            }
+            // Remove all the contributor info from storage in a single write.
+            // This is possible thanks to the use of a child tree.
+            Self::crowdfund_kill(index);
         }
 
-        /// Remove the entire record of contributions in the associated child trie in a single
-        /// storage write.
+        /// Remove the entire record of contributions, their details and private contributions
+        /// in the associated child tries, with a single storage write each. Private
+        /// contributions that are still claimable after a slash are kept.
         pub fn crowdfund_kill(index: FundIndex) {
             let id = Self::id_from_index(index);
             // The None here means we aren't setting a limit to how many keys to delete.
             // Limiting can be useful, but is beyond the scope of this recipe. For more info, see
             // https://crates.parity.io/frame_support/storage/child/fn.kill_storage.html
+            // Failed funds can have their entries cleared in bounded batches with `refund_all`.
             child::kill_storage(&id, None);
+            child::kill_storage(&Self::detail_id_from_index(index), None);
+            if !<UnclaimedPrivate<T>>::contains_key(index) {
+                child::kill_storage(&Self::private_id_from_index(index), None);
+            }
         }
     }
 }
@@ -1,11 +1,21 @@
 use crate as pallet_crowdfund;
-use frame_support::parameter_types;
+use crate::{
+    BurnResidue, DepositFor, FixedDeposit, HandleDissolveResidue, OnFundSuccess, ResidueTo,
+    RewardDissolver, WeightInfo,
+};
+use frame_support::{
+    parameter_types,
+    traits::{Currency, Get, OnUnbalanced},
+    weights::Weight,
+};
 use frame_system as system;
 use sp_core::H256;
 use sp_runtime::{
     testing::Header,
-    traits::{BlakeTwo256, IdentityLookup},
+    traits::{BlakeTwo256, ConvertInto, IdentityLookup},
+    Perbill,
 };
+use std::cell::RefCell;
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 type Block = frame_system::mocking::MockBlock<Test>;
@@ -19,6 +29,7 @@ frame_support::construct_runtime!(
     {
         System: frame_system::{Module, Call, Config, Storage, Event<T>},
         Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
+        Vesting: pallet_vesting::{Module, Call, Storage, Event<T>},
         PalletCrowdfund: pallet_crowdfund::{Module, Call, Storage, Event<T>},
     }
 );
@@ -67,24 +78,207 @@ impl pallet_balances::Config for Test {
     type WeightInfo = ();
 }
 
+parameter_types! {
+    pub const MinVestedTransfer: u64 = 256;
+}
+
+impl pallet_vesting::Config for Test {
+    type Event = Event;
+    type Currency = Balances;
+    type BlockNumberToBalance = ConvertInto;
+    type MinVestedTransfer = MinVestedTransfer;
+    type WeightInfo = ();
+}
+
 parameter_types! {
     pub const SubmissionDeposit: u64 = 10000;
     pub const MinContribution: u64 = 100;
-    pub const RetirementPeriod: u32 = 10;
+    pub const MinGoal: u64 = 100;
+    pub const MaxDuration: u64 = 100;
+    pub const RetirementPeriod: u64 = 10;
+    pub const MaxSplits: u32 = 4;
+    pub const AllowContributorDeath: bool = false;
+    pub const MaxStretchGoals: u32 = 3;
+    pub const MaxContributors: u32 = 4;
+    pub const MaxAutoDispense: u32 = 2;
+}
+
+thread_local! {
+    /// Every `(beneficiary, raised)` pair passed to `RecordSuccess`, in order.
+    pub static SUCCESSES: RefCell<Vec<(u64, u64)>> = RefCell::new(Vec::new());
+    /// The weight `MockWeights` charges per contributor refunded.
+    pub static REFUND_ENTRY_WEIGHT: RefCell<Weight> = RefCell::new(1_000);
+    /// When set, `MockDeposit` charges this share of the goal instead of `SubmissionDeposit`.
+    pub static DEPOSIT_SHARE: RefCell<Option<Perbill>> = RefCell::new(None);
+    /// The value of `CreationCooldown`. Zero unless a test needs it, so funds can be created
+    /// back to back.
+    pub static CREATION_COOLDOWN: RefCell<u64> = RefCell::new(0);
+    /// The value of `ContributionFee`. No fee unless a test sets one.
+    pub static CONTRIBUTION_FEE: RefCell<Perbill> = RefCell::new(Perbill::zero());
+    /// The value of `AllowContributionsWhileFrozen`.
+    pub static FROZEN_CONTRIBUTIONS: RefCell<bool> = RefCell::new(false);
+}
+
+pub struct AllowContributionsWhileFrozen;
+impl Get<bool> for AllowContributionsWhileFrozen {
+    fn get() -> bool {
+        FROZEN_CONTRIBUTIONS.with(|allowed| *allowed.borrow())
+    }
+}
+
+pub struct ContributionFee;
+impl Get<Perbill> for ContributionFee {
+    fn get() -> Perbill {
+        CONTRIBUTION_FEE.with(|fee| *fee.borrow())
+    }
+}
+
+pub struct CreationCooldown;
+impl Get<u64> for CreationCooldown {
+    fn get() -> u64 {
+        CREATION_COOLDOWN.with(|cooldown| *cooldown.borrow())
+    }
+}
+
+/// A flat `SubmissionDeposit`, or a share of the goal once `DEPOSIT_SHARE` is set.
+pub struct MockDeposit;
+impl DepositFor<u64> for MockDeposit {
+    fn deposit_for(goal: u64) -> u64 {
+        DEPOSIT_SHARE.with(|share| match *share.borrow() {
+            Some(share) => share * goal,
+            None => FixedDeposit::<SubmissionDeposit>::deposit_for(goal),
+        })
+    }
+}
+
+/// Records every successful dispense in `SUCCESSES`.
+pub struct RecordSuccess;
+impl OnFundSuccess<u64, u64> for RecordSuccess {
+    fn on_success(beneficiary: &u64, raised: u64) {
+        SUCCESSES.with(|s| s.borrow_mut().push((*beneficiary, raised)));
+    }
+}
+
+/// Weights with a fixed base cost of 10_000 and a per-contributor cost read from
+/// `REFUND_ENTRY_WEIGHT`.
+pub struct MockWeights;
+impl WeightInfo for MockWeights {
+    fn refund_all(c: u32) -> Weight {
+        10_000 + REFUND_ENTRY_WEIGHT.with(|w| *w.borrow()) * c as Weight
+    }
+    fn report_and_slash(c: u32) -> Weight {
+        10_000 + REFUND_ENTRY_WEIGHT.with(|w| *w.borrow()) * c as Weight
+    }
+    fn dissolve(c: u32) -> Weight {
+        10_000 + REFUND_ENTRY_WEIGHT.with(|w| *w.borrow()) * c as Weight
+    }
+    fn on_initialize(d: u32) -> Weight {
+        10_000 * (1 + d as Weight)
+    }
+}
+
+/// The account that receives seized deposits in tests.
+pub const TREASURY: u64 = 100;
+
+/// The residue policies `MockResidue` can switch between.
+#[derive(Clone, Copy)]
+pub enum ResiduePolicy {
+    Reward,
+    Burn,
+    Treasury,
+}
+
+thread_local! {
+    /// The policy `MockResidue` applies. Rewards the dissolver unless a test changes it.
+    pub static RESIDUE_POLICY: RefCell<ResiduePolicy> = RefCell::new(ResiduePolicy::Reward);
+}
+
+type NegativeImbalance = <Balances as Currency<u64>>::NegativeImbalance;
+
+/// Sends slashed deposits to the `TREASURY` account.
+pub struct SlashToTreasury;
+impl OnUnbalanced<NegativeImbalance> for SlashToTreasury {
+    fn on_nonzero_unbalanced(amount: NegativeImbalance) {
+        Balances::resolve_creating(&TREASURY, amount);
+    }
+}
+
+/// Applies whichever of the pallet's residue handlers `RESIDUE_POLICY` selects.
+pub struct MockResidue;
+impl HandleDissolveResidue<u64, NegativeImbalance> for MockResidue {
+    fn handle(dissolver: &u64, residue: NegativeImbalance) {
+        match RESIDUE_POLICY.with(|policy| *policy.borrow()) {
+            ResiduePolicy::Reward => RewardDissolver::<Balances>::handle(dissolver, residue),
+            ResiduePolicy::Burn => BurnResidue::handle(dissolver, residue),
+            ResiduePolicy::Treasury => ResidueTo::<SlashToTreasury>::handle(dissolver, residue),
+        }
+    }
 }
 
 impl pallet_crowdfund::Config for Test {
     type Event = Event;
     type Currency = Balances;
-    type SubmissionDeposit = SubmissionDeposit;
+    type DepositCalculator = MockDeposit;
     type MinContribution = MinContribution;
+    type MinGoal = MinGoal;
+    type AllowContributorDeath = AllowContributorDeath;
+    type AllowContributionsWhileFrozen = AllowContributionsWhileFrozen;
+    type CreationCooldown = CreationCooldown;
+    type MaxDuration = MaxDuration;
     type RetirementPeriod = RetirementPeriod;
+    type ContributionFee = ContributionFee;
+    type MaxSplits = MaxSplits;
+    type MaxStretchGoals = MaxStretchGoals;
+    type VestingSchedule = Vesting;
+    type OnSuccess = RecordSuccess;
+    type AdminOrigin = system::EnsureRoot<u64>;
+    type OnSlash = SlashToTreasury;
+    type DissolveDestination = MockResidue;
+    type MaxContributors = MaxContributors;
+    type MaxAutoDispense = MaxAutoDispense;
+    type WeightInfo = MockWeights;
+}
+
+/// Every event the pallet has deposited so far, oldest first.
+pub fn events() -> Vec<pallet_crowdfund::Event<Test>> {
+    System::events()
+        .into_iter()
+        .filter_map(|record| match record.event {
+            Event::pallet_crowdfund(inner) => Some(inner),
+            _ => None,
+        })
+        .collect()
 }
 
-// Build genesis storage according to the mock runtime.
+/// The most recent event the pallet deposited.
+pub fn last_event() -> pallet_crowdfund::Event<Test> {
+    events().pop().expect("a crowdfund event was deposited")
+}
+
+/// Assert that the most recent event the pallet deposited is `event`.
+pub fn assert_last_event(event: pallet_crowdfund::Event<Test>) {
+    assert_eq!(last_event(), event);
+}
+
+/// Build genesis storage for the mock runtime, with accounts 1 to 5 holding 1_000_000 each and
+/// the chain at block 1.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-    system::GenesisConfig::default()
+    let mut t = system::GenesisConfig::default()
         .build_storage::<Test>()
-        .unwrap()
-        .into()
+        .unwrap();
+    pallet_balances::GenesisConfig::<Test> {
+        balances: vec![
+            (1, 1_000_000),
+            (2, 1_000_000),
+            (3, 1_000_000),
+            (4, 1_000_000),
+            (5, 1_000_000),
+        ],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+
+    let mut ext: sp_io::TestExternalities = t.into();
+    ext.execute_with(|| System::set_block_number(1));
+    ext
 }
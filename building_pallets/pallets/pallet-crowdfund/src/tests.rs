@@ -1,26 +1,2530 @@
-use crate::{mock::*, Error};
-use frame_support::{assert_noop, assert_ok, dispatch::DispatchError};
+use crate::{
+    mock::*, Category, Error, Event as CrowdfundEvent, FundParams, RefundReason, WeightInfo,
+};
+use codec::Encode;
+use frame_support::traits::{
+    Currency, Get, OnFinalize, OnInitialize, ReservableCurrency, VestingSchedule,
+};
+use frame_support::{
+    assert_noop, assert_ok,
+    dispatch::{DispatchError, DispatchResultWithPostInfo, WithPostDispatchInfo},
+    storage::child,
+    weights::GetDispatchInfo,
+};
+use sp_runtime::{
+    traits::{AccountIdConversion, BlakeTwo256, Hash},
+    ModuleId, Perbill,
+};
+
+/// Create a fund with no optional features configured.
+fn create_fund(creator: u64, beneficiary: u64, goal: u64, end: u64) -> DispatchResultWithPostInfo {
+    PalletCrowdfund::create(
+        Origin::signed(creator),
+        beneficiary,
+        goal,
+        end,
+        FundParams::default(),
+    )
+}
+
+#[test]
+fn create_rejects_unsigned_origin() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            PalletCrowdfund::create(Origin::none(), 123456789, 30000, 10, FundParams::default()),
+            DispatchError::BadOrigin,
+        );
+    });
+}
+
+#[test]
+fn create_stores_fund_and_emits_created() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PalletCrowdfund::create(
+            Origin::signed(1),
+            123456789,
+            30000,
+            10,
+            FundParams::default()
+        ));
+        assert_eq!(PalletCrowdfund::fund_count(), 1);
+        assert_last_event(CrowdfundEvent::Created(0, 1));
+    });
+}
+
+#[test]
+fn create_fails_when_end_is_not_in_the_future() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(create_fund(1, 2, 30000, 1), Error::<Test>::EndTooEarly,);
+    });
+}
+
+#[test]
+fn create_records_the_creation_block() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(5);
+        assert_ok!(create_fund(1, 2, 30000, 10));
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().created, 5);
+    });
+}
+
+#[test]
+fn withdraw_returns_contribution_after_end() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 500, None));
+        assert_noop!(
+            PalletCrowdfund::withdraw(Origin::signed(3), 0),
+            Error::<Test>::FundStillActive,
+        );
+
+        System::set_block_number(11);
+        assert_ok!(PalletCrowdfund::withdraw(Origin::signed(3), 0));
+        assert_eq!(Balances::free_balance(3), 1_000_000);
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().raised, 0);
+        assert_eq!(PalletCrowdfund::contribution_get(0, &3), 0);
+        assert_noop!(
+            PalletCrowdfund::withdraw(Origin::signed(3), 0),
+            Error::<Test>::NoContribution,
+        );
+    });
+}
+
+#[test]
+fn dispense_pays_beneficiary_and_reports_amount() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 600, None));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(4), 0, 400, None));
+
+        System::set_block_number(10);
+        assert_ok!(PalletCrowdfund::dispense(Origin::signed(1), 0));
+
+        assert_eq!(Balances::free_balance(2), 1_001_000);
+        assert_eq!(Balances::free_balance(1), 1_000_000 - 10_000);
+        assert_eq!(PalletCrowdfund::funds(0), None);
+        assert_last_event(CrowdfundEvent::Dispensed(0, 10, 1, 1000));
+    });
+}
+
+#[test]
+fn dispense_fails_for_unsuccessful_fund() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 600, None));
+
+        System::set_block_number(10);
+        assert_noop!(
+            PalletCrowdfund::dispense(Origin::signed(1), 0),
+            Error::<Test>::UnsuccessfulFund,
+        );
+    });
+}
+
+#[test]
+fn dissolve_sweeps_pot_and_reports_amount() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 500, None));
+
+        System::set_block_number(19);
+        assert_noop!(
+            PalletCrowdfund::dissolve(Origin::signed(4), 0),
+            Error::<Test>::FundNotRetired,
+        );
+
+        System::set_block_number(20);
+        assert_ok!(PalletCrowdfund::dissolve(Origin::signed(4), 0));
+
+        assert_eq!(Balances::free_balance(4), 1_010_500);
+        assert_eq!(PalletCrowdfund::funds(0), None);
+        assert_last_event(CrowdfundEvent::Dissolved(0, 20, 4, 10_500));
+    });
+}
+
+#[test]
+fn dispense_splits_payout_between_beneficiaries() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PalletCrowdfund::create(
+            Origin::signed(1),
+            2,
+            1000,
+            10,
+            FundParams {
+                splits: vec![(3, Perbill::from_percent(30))],
+                ..Default::default()
+            }
+        ));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(4),
+            0,
+            1001,
+            None
+        ));
+
+        System::set_block_number(10);
+        assert_ok!(PalletCrowdfund::dispense(Origin::signed(1), 0));
+
+        // 30% of 1001 rounds down to 300, the remainder goes to the primary beneficiary
+        assert_eq!(Balances::free_balance(3), 1_000_300);
+        assert_eq!(Balances::free_balance(2), 1_000_701);
+        assert_last_event(CrowdfundEvent::Dispensed(0, 10, 1, 1001));
+    });
+}
+
+#[test]
+fn create_rejects_split_above_the_whole() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            PalletCrowdfund::create(
+                Origin::signed(1),
+                2,
+                1000,
+                10,
+                FundParams {
+                    splits: vec![
+                        (3, Perbill::from_percent(70)),
+                        (4, Perbill::from_percent(31))
+                    ],
+                    ..Default::default()
+                }
+            ),
+            Error::<Test>::InvalidSplit,
+        );
+        assert_noop!(
+            PalletCrowdfund::create(
+                Origin::signed(1),
+                2,
+                1000,
+                10,
+                FundParams {
+                    splits: vec![(3, Perbill::zero()); 5],
+                    ..Default::default()
+                }
+            ),
+            Error::<Test>::TooManySplits,
+        );
+    });
+}
+
+#[test]
+fn dispense_vests_payout_when_configured() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PalletCrowdfund::create(
+            Origin::signed(1),
+            2,
+            1000,
+            10,
+            FundParams {
+                vesting_blocks: Some(10),
+                ..Default::default()
+            }
+        ));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(3),
+            0,
+            1000,
+            None
+        ));
+
+        System::set_block_number(10);
+        assert_ok!(PalletCrowdfund::dispense(Origin::signed(1), 0));
+
+        // The payout arrived but none of it is spendable yet
+        assert_eq!(Balances::free_balance(2), 1_001_000);
+        assert_eq!(Vesting::vesting_balance(&2), Some(1000));
+        assert_eq!(Balances::usable_balance(&2), 1_000_000);
+        assert!(Balances::transfer(Origin::signed(2), 3, 1_000_500).is_err());
+    });
+}
+
+#[test]
+fn existing_schedule_does_not_bypass_vesting() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PalletCrowdfund::create(
+            Origin::signed(1),
+            2,
+            1000,
+            10,
+            FundParams {
+                vesting_blocks: Some(10),
+                ..Default::default()
+            }
+        ));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(3),
+            0,
+            1000,
+            None
+        ));
+        // Anyone can give the beneficiary a schedule of their own before the fund ends
+        assert_ok!(<Vesting as VestingSchedule<u64>>::add_vesting_schedule(
+            &2, 500, 50, 1
+        ));
+
+        System::set_block_number(10);
+        assert_ok!(PalletCrowdfund::dispense(Origin::signed(1), 0));
+
+        // Nothing is paid out unlocked, the pot holds the payout back instead
+        let pot = PalletCrowdfund::fund_account_id(0);
+        assert_eq!(Balances::free_balance(2), 1_000_000);
+        assert_eq!(Vesting::vesting_balance(&2), Some(50));
+        assert!(events().contains(&CrowdfundEvent::PayoutHeld(0, 2, 1000)));
+        assert_noop!(
+            PalletCrowdfund::release_vested(Origin::signed(4), 0),
+            Error::<Test>::NothingToRelease
+        );
+
+        // Claiming the deposit leaves the held payout in the pot
+        assert_ok!(PalletCrowdfund::claim_deposit(Origin::signed(1), 0));
+        assert_eq!(Balances::free_balance(&pot), 1000);
+
+        // It is released at the rate the fund set, 100 per block
+        System::set_block_number(13);
+        assert_ok!(PalletCrowdfund::release_vested(Origin::signed(4), 0));
+        assert_eq!(Balances::free_balance(2), 1_000_300);
+        assert_last_event(CrowdfundEvent::PayoutReleased(0, 2, 300));
+
+        System::set_block_number(30);
+        assert_ok!(PalletCrowdfund::release_vested(Origin::signed(4), 0));
+        assert_eq!(Balances::free_balance(2), 1_001_000);
+        assert_eq!(Balances::free_balance(&pot), 0);
+        assert_eq!(PalletCrowdfund::held_payout(0), None);
+        assert_noop!(
+            PalletCrowdfund::release_vested(Origin::signed(4), 0),
+            Error::<Test>::NoHeldPayout
+        );
+    });
+}
+
+#[test]
+fn create_rejects_zero_vesting_period() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            PalletCrowdfund::create(
+                Origin::signed(1),
+                2,
+                1000,
+                10,
+                FundParams {
+                    vesting_blocks: Some(0),
+                    ..Default::default()
+                }
+            ),
+            Error::<Test>::InvalidVestingPeriod,
+        );
+    });
+}
+
+#[test]
+fn goal_progress_tracks_raised_amount() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(PalletCrowdfund::goal_progress(0), None);
+
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_eq!(PalletCrowdfund::goal_progress(0), Some(Perbill::zero()));
+
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 500, None));
+        assert_eq!(
+            PalletCrowdfund::goal_progress(0),
+            Some(Perbill::from_percent(50))
+        );
+
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(4), 0, 500, None));
+        assert_eq!(PalletCrowdfund::goal_progress(0), Some(Perbill::one()));
+
+        // Raising beyond the goal is clamped to 100%
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(4), 0, 500, None));
+        assert_eq!(PalletCrowdfund::goal_progress(0), Some(Perbill::one()));
+    });
+}
+
+#[test]
+fn goal_progress_of_zero_goal_is_complete() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        // `MinGoal` keeps this out of reach of `create`, but a runtime may configure it as zero
+        crate::Funds::<Test>::mutate(0, |fund| fund.as_mut().unwrap().goal = 0);
+        assert_eq!(PalletCrowdfund::goal_progress(0), Some(Perbill::one()));
+    });
+}
+
+#[test]
+fn contributed_event_distinguishes_new_backers() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 100, None));
+        assert_last_event(CrowdfundEvent::Contributed(3, 0, 100, 1, true));
+
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 200, None));
+        assert_last_event(CrowdfundEvent::Contributed(3, 0, 300, 1, false));
+
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(4), 0, 100, None));
+        assert_last_event(CrowdfundEvent::Contributed(4, 0, 100, 1, true));
+    });
+}
+
+#[test]
+fn fund_reaching_soft_cap_is_dispensable() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PalletCrowdfund::create(
+            Origin::signed(1),
+            2,
+            1000,
+            10,
+            FundParams {
+                soft_cap: Some(600),
+                ..Default::default()
+            }
+        ));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 600, None));
+
+        System::set_block_number(10);
+        assert_ok!(PalletCrowdfund::dispense(Origin::signed(1), 0));
+        assert_eq!(Balances::free_balance(2), 1_000_600);
+    });
+}
+
+#[test]
+fn fund_below_soft_cap_is_not_dispensable() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PalletCrowdfund::create(
+            Origin::signed(1),
+            2,
+            1000,
+            10,
+            FundParams {
+                soft_cap: Some(600),
+                ..Default::default()
+            }
+        ));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 500, None));
+
+        System::set_block_number(10);
+        assert_noop!(
+            PalletCrowdfund::dispense(Origin::signed(1), 0),
+            Error::<Test>::UnsuccessfulFund,
+        );
+    });
+}
+
+#[test]
+fn create_rejects_soft_cap_above_goal() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            PalletCrowdfund::create(
+                Origin::signed(1),
+                2,
+                1000,
+                10,
+                FundParams {
+                    soft_cap: Some(1001),
+                    ..Default::default()
+                }
+            ),
+            Error::<Test>::InvalidSoftCap,
+        );
+    });
+}
+
+#[test]
+fn active_funds_pages_through_remaining_funds() {
+    new_test_ext().execute_with(|| {
+        for _ in 0..5 {
+            assert_ok!(create_fund(1, 2, 1000, 10));
+        }
+        // Fund 5 is dispensed early so the last page skips over it
+        assert_ok!(create_fund(1, 2, 100, 2));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 5, 100, None));
+        System::set_block_number(2);
+        assert_ok!(PalletCrowdfund::dispense(Origin::signed(1), 5));
+        assert_ok!(create_fund(1, 2, 1000, 10));
+
+        let indices = |page: &[(u32, _)]| page.iter().map(|(i, _)| *i).collect::<Vec<_>>();
+
+        let (page, next) = PalletCrowdfund::active_funds(0, 2);
+        assert_eq!(indices(&page), vec![0, 1]);
+        assert_eq!(next, Some(2));
+
+        let (page, next) = PalletCrowdfund::active_funds(2, 2);
+        assert_eq!(indices(&page), vec![2, 3]);
+        assert_eq!(next, Some(4));
+
+        let (page, next) = PalletCrowdfund::active_funds(4, 2);
+        assert_eq!(indices(&page), vec![4, 6]);
+        assert_eq!(next, None);
+        assert_eq!(page[1].1, PalletCrowdfund::funds(6).unwrap());
+    });
+}
+
+#[test]
+fn active_funds_bounds_the_indices_it_reads() {
+    new_test_ext().execute_with(|| {
+        for index in 0..10 {
+            assert_ok!(create_fund(1, 2, 100, 2));
+            assert_ok!(PalletCrowdfund::contribute(
+                Origin::signed(3),
+                index,
+                100,
+                None
+            ));
+        }
+        System::set_block_number(2);
+        for index in 0..10 {
+            assert_ok!(PalletCrowdfund::dispense(Origin::signed(1), index));
+        }
+        assert_ok!(create_fund(1, 2, 1000, 10));
+
+        // A page of one reads at most four indices, all of them removed here
+        let (page, next) = PalletCrowdfund::active_funds(0, 1);
+        assert!(page.is_empty());
+        assert_eq!(next, Some(4));
+
+        let (page, next) = PalletCrowdfund::active_funds(8, 1);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].0, 10);
+        assert_eq!(next, None);
+    });
+}
+
+#[test]
+fn contributor_count_is_bounded() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        for who in 2..=5 {
+            assert_ok!(PalletCrowdfund::contribute(
+                Origin::signed(who),
+                0,
+                100,
+                None
+            ));
+        }
+        assert_eq!(PalletCrowdfund::contributor_count(0), 4);
+
+        // Existing contributors may still top up, but a fifth one is turned away
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(2), 0, 100, None));
+        assert_noop!(
+            PalletCrowdfund::contribute(Origin::signed(1), 0, 100, None),
+            Error::<Test>::TooManyContributors,
+        );
+    });
+}
 
 #[test]
-fn correct_error_for_unsigned_origin_while_creating_task_with_correct_() {
+fn report_and_slash_refunds_contributors_and_seizes_deposit() {
     new_test_ext().execute_with(|| {
-        // Ensure the expected error is thrown when no value is present.
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 500, None));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(4), 0, 300, None));
+
         assert_noop!(
-            PalletCrowdfund::create(Origin::none(), 123456789, 30000, 10),
+            PalletCrowdfund::report_and_slash(Origin::signed(1), 0),
             DispatchError::BadOrigin,
         );
+        assert_ok!(PalletCrowdfund::report_and_slash(Origin::root(), 0));
+
+        assert_eq!(Balances::free_balance(3), 1_000_000);
+        assert_eq!(Balances::free_balance(4), 1_000_000);
+        assert_eq!(Balances::free_balance(1), 990_000);
+        assert_eq!(Balances::free_balance(TREASURY), 10_000);
+        assert_eq!(
+            Balances::free_balance(PalletCrowdfund::fund_account_id(0)),
+            0
+        );
+
+        assert_eq!(PalletCrowdfund::funds(0), None);
+        assert_eq!(PalletCrowdfund::contributor_count(0), 0);
+        assert_eq!(PalletCrowdfund::contribution_get(0, &3), 0);
+        assert_last_event(CrowdfundEvent::Slashed(0, 1, 10_000));
+    });
+}
+
+#[test]
+fn report_and_slash_leaves_private_contributions_claimable() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 500, None));
+        let secret = b"correct horse".to_vec();
+        let commitment = BlakeTwo256::hash_of(&(4u64, secret.clone()));
+        assert_ok!(PalletCrowdfund::contribute_private(
+            Origin::signed(4),
+            0,
+            300,
+            commitment,
+            None
+        ));
+
+        assert_ok!(PalletCrowdfund::report_and_slash(Origin::root(), 0));
+
+        // Only the deposit is seized, the private contribution stays in the pot
+        assert_eq!(Balances::free_balance(TREASURY), 10_000);
+        assert_eq!(Balances::free_balance(3), 1_000_000);
+        assert_eq!(
+            Balances::free_balance(PalletCrowdfund::fund_account_id(0)),
+            300
+        );
+        assert_eq!(PalletCrowdfund::funds(0), None);
+        assert_eq!(PalletCrowdfund::unclaimed_private(0), Some(300));
+
+        assert_noop!(
+            PalletCrowdfund::withdraw_private(Origin::signed(4), 0, b"wrong".to_vec()),
+            Error::<Test>::NoContribution
+        );
+        assert_ok!(PalletCrowdfund::withdraw_private(
+            Origin::signed(4),
+            0,
+            secret
+        ));
+        assert_eq!(Balances::free_balance(4), 1_000_000);
+        assert_eq!(PalletCrowdfund::unclaimed_private(0), None);
+        assert_eq!(PalletCrowdfund::private_contribution_get(0, &commitment), 0);
+        assert_last_event(CrowdfundEvent::ContributionRefunded(
+            4,
+            0,
+            300,
+            RefundReason::Slash,
+            1,
+        ));
+    });
+}
+
+#[test]
+fn report_and_slash_rejects_a_retired_fund() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 500, None));
+
+        System::set_block_number(19);
+        assert_ok!(PalletCrowdfund::report_and_slash(Origin::root(), 0));
+
+        assert_ok!(create_fund(4, 2, 1000, 30));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 1, 500, None));
+        System::set_block_number(40);
+        assert_noop!(
+            PalletCrowdfund::report_and_slash(Origin::root(), 1),
+            Error::<Test>::RefundPeriodExpired
+        );
+    });
+}
+
+/// A tiny xorshift generator so randomized tests are reproducible from their seed.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+}
+
+/// The pot holds exactly the deposit plus everything raised, `raised` is the sum of the
+/// recorded contributions and the fees, and the contributor count matches the non-zero entries.
+fn assert_accounting_invariant(index: u32) {
+    let fund = PalletCrowdfund::funds(index).unwrap();
+    let pot = Balances::free_balance(PalletCrowdfund::fund_account_id(index));
+    assert_eq!(pot, fund.raised + fund.deposit);
+
+    let contributions = PalletCrowdfund::contributions(index, u32::max_value());
+    let total: u64 = contributions.iter().map(|(_, balance)| balance).sum();
+    assert_eq!(total + fund.fees, fund.raised);
+    let non_zero = contributions
+        .iter()
+        .filter(|(_, balance)| *balance > 0)
+        .count() as u32;
+    assert_eq!(PalletCrowdfund::contributor_count(index), non_zero);
+}
+
+#[test]
+fn random_contributions_and_withdrawals_keep_accounts_balanced() {
+    for seed in 1..=50 {
+        new_test_ext().execute_with(|| {
+            let mut rng = Rng(seed);
+            assert_ok!(create_fund(1, 2, 1000, 10));
+
+            for _ in 0..40 {
+                let who = 1 + rng.below(5);
+                // Calls are allowed to fail; the invariant must hold either way.
+                let _ = match rng.below(5) {
+                    0 | 1 => {
+                        PalletCrowdfund::contribute(Origin::signed(who), 0, rng.below(1000), None)
+                    }
+                    2 | 3 => PalletCrowdfund::withdraw(Origin::signed(who), 0),
+                    _ => {
+                        System::set_block_number(System::block_number() + 1 + rng.below(3));
+                        Ok(().into())
+                    }
+                };
+                assert_accounting_invariant(0);
+            }
+        });
+    }
+}
+
+#[test]
+fn refund_all_sweeps_failed_fund_in_batches() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        for who in 3..=5 {
+            assert_ok!(PalletCrowdfund::contribute(
+                Origin::signed(who),
+                0,
+                200,
+                None
+            ));
+        }
+
+        assert_noop!(
+            PalletCrowdfund::refund_all(Origin::signed(1), 0, Some(2)),
+            Error::<Test>::FundStillActive,
+        );
+
+        System::set_block_number(11);
+        assert_ok!(PalletCrowdfund::refund_all(Origin::signed(1), 0, Some(2)));
+        assert_last_event(CrowdfundEvent::BatchRefunded(0, 2, true));
+        assert_eq!(PalletCrowdfund::contributor_count(0), 1);
+
+        assert_ok!(PalletCrowdfund::refund_all(Origin::signed(1), 0, Some(2)));
+        assert_last_event(CrowdfundEvent::BatchRefunded(0, 1, false));
+
+        for who in 3..=5 {
+            assert_eq!(Balances::free_balance(who), 1_000_000);
+        }
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().raised, 0);
+        assert_eq!(PalletCrowdfund::contributor_count(0), 0);
+        assert!(PalletCrowdfund::contributions(0, u32::max_value()).is_empty());
+    });
+}
+
+#[test]
+fn refund_all_closes_when_retirement_period_ends() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        for who in 3..=4 {
+            assert_ok!(PalletCrowdfund::contribute(
+                Origin::signed(who),
+                0,
+                200,
+                None
+            ));
+        }
+
+        System::set_block_number(19);
+        assert_ok!(PalletCrowdfund::refund_all(Origin::signed(1), 0, Some(1)));
+
+        System::set_block_number(20);
+        assert_noop!(
+            PalletCrowdfund::refund_all(Origin::signed(1), 0, None),
+            Error::<Test>::RefundPeriodExpired
+        );
+        assert_ok!(PalletCrowdfund::dissolve(Origin::signed(5), 0));
+        assert_eq!(Balances::free_balance(5), 1_000_000 + 10_000 + 200);
+    });
+}
+
+#[test]
+fn refund_all_rejects_successful_fund() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(3),
+            0,
+            1000,
+            None
+        ));
+
+        System::set_block_number(11);
+        assert_noop!(
+            PalletCrowdfund::refund_all(Origin::signed(1), 0, Some(2)),
+            Error::<Test>::SuccessfulFund,
+        );
+    });
+}
+
+#[test]
+fn withdraw_recreates_a_reaped_contributor() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 500, None));
+        // The contributor empties their account, which reaps it
+        assert_ok!(Balances::transfer(Origin::signed(3), 4, 999_500));
+        assert!(!System::account_exists(&3));
+
+        System::set_block_number(11);
+        assert_ok!(PalletCrowdfund::withdraw(Origin::signed(3), 0));
+        assert_eq!(Balances::free_balance(3), 500);
+        assert_eq!(
+            Balances::free_balance(PalletCrowdfund::fund_account_id(0)),
+            10_000
+        );
+    });
+}
+
+#[test]
+fn has_contributed_follows_contribution_lifecycle() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert!(!PalletCrowdfund::has_contributed(0, &3));
+
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 500, None));
+        assert!(PalletCrowdfund::has_contributed(0, &3));
+        assert!(!PalletCrowdfund::has_contributed(0, &4));
+
+        System::set_block_number(11);
+        assert_ok!(PalletCrowdfund::withdraw(Origin::signed(3), 0));
+        assert!(!PalletCrowdfund::has_contributed(0, &3));
     });
 }
 
-// #[test]
-// fn it_works_when_creating_with_correct_details() {
-//     new_test_ext().execute_with(|| {
-//         // Ensure the expected error is thrown when no value is present.
-//         assert_ok!(PalletCrowdfund::create(
-//             Origin::signed(1),
-//             123456789,
-//             30000,
-//             10
-//         ));
-//     });
-// }
+#[test]
+fn large_contribution_reaches_several_stretch_goals_in_order() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PalletCrowdfund::create(
+            Origin::signed(1),
+            2,
+            1000,
+            10,
+            FundParams {
+                stretch_goals: vec![1500, 2000, 3000],
+                ..Default::default()
+            }
+        ));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(3),
+            0,
+            1000,
+            None
+        ));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(4),
+            0,
+            1200,
+            None
+        ));
+
+        let events = events();
+        assert_eq!(
+            events[events.len() - 3..],
+            [
+                CrowdfundEvent::Contributed(4, 0, 1200, 1, true),
+                CrowdfundEvent::StretchReached(0, 0, 1),
+                CrowdfundEvent::StretchReached(0, 1, 1),
+            ]
+        );
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().stretch_reached, 2);
+
+        // Already reached stretch goals do not fire again
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 100, None));
+        assert_last_event(CrowdfundEvent::Contributed(3, 0, 1100, 1, false));
+    });
+}
+
+#[test]
+fn create_rejects_unordered_stretch_goals() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            PalletCrowdfund::create(
+                Origin::signed(1),
+                2,
+                1000,
+                10,
+                FundParams {
+                    stretch_goals: vec![2000, 1500],
+                    ..Default::default()
+                }
+            ),
+            Error::<Test>::InvalidStretchGoals,
+        );
+        assert_noop!(
+            PalletCrowdfund::create(
+                Origin::signed(1),
+                2,
+                1000,
+                10,
+                FundParams {
+                    stretch_goals: vec![1000],
+                    ..Default::default()
+                }
+            ),
+            Error::<Test>::InvalidStretchGoals,
+        );
+    });
+}
+
+#[test]
+fn on_success_fires_once_per_dispense() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(create_fund(1, 3, 500, 10));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(4),
+            0,
+            1200,
+            None
+        ));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(4), 1, 500, None));
+
+        System::set_block_number(10);
+        assert_ok!(PalletCrowdfund::dispense(Origin::signed(1), 0));
+        assert_ok!(PalletCrowdfund::dispense(Origin::signed(1), 1));
+        assert_noop!(
+            PalletCrowdfund::dispense(Origin::signed(1), 1),
+            Error::<Test>::InvalidIndex,
+        );
+
+        SUCCESSES.with(|s| assert_eq!(*s.borrow(), vec![(2, 1200), (3, 500)]));
+    });
+}
+
+#[test]
+fn contribution_that_would_reap_contributor_is_rejected() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+
+        assert!(PalletCrowdfund::contribute(Origin::signed(5), 0, 1_000_000, None).is_err());
+        assert_eq!(Balances::free_balance(5), 1_000_000);
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().raised, 0);
+        assert!(!PalletCrowdfund::has_contributed(0, &5));
+
+        // Leaving the existential deposit behind is fine
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(5),
+            0,
+            999_999,
+            None
+        ));
+        assert_eq!(Balances::free_balance(5), 1);
+    });
+}
+
+#[test]
+fn repeated_client_ref_is_rejected() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(3),
+            0,
+            100,
+            Some(7)
+        ));
+        assert_noop!(
+            PalletCrowdfund::contribute(Origin::signed(3), 0, 100, Some(7)),
+            Error::<Test>::DuplicateContribution,
+        );
+
+        // A new reference, or another contributor reusing the same one, goes through
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(3),
+            0,
+            100,
+            Some(8)
+        ));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(4),
+            0,
+            100,
+            Some(7)
+        ));
+        assert_eq!(PalletCrowdfund::contribution_get(0, &3), 200);
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().raised, 300);
+    });
+}
+
+#[test]
+fn contribution_detail_keeps_first_contribution_block() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_eq!(PalletCrowdfund::contribution_detail(0, &3), None);
+
+        System::set_block_number(2);
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 100, None));
+        assert_eq!(PalletCrowdfund::contribution_detail(0, &3), Some((2, 100)));
+
+        System::set_block_number(5);
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 250, None));
+        assert_eq!(PalletCrowdfund::contribution_detail(0, &3), Some((2, 350)));
+
+        System::set_block_number(11);
+        assert_ok!(PalletCrowdfund::withdraw(Origin::signed(3), 0));
+        assert_eq!(PalletCrowdfund::contribution_detail(0, &3), None);
+    });
+}
+
+#[test]
+fn fund_short_of_min_contributors_is_not_dispensable() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PalletCrowdfund::create(
+            Origin::signed(1),
+            2,
+            1000,
+            10,
+            FundParams {
+                min_contributors: Some(3),
+                ..Default::default()
+            }
+        ));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 600, None));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(4), 0, 600, None));
+
+        System::set_block_number(10);
+        assert_noop!(
+            PalletCrowdfund::dispense(Origin::signed(1), 0),
+            Error::<Test>::UnsuccessfulFund,
+        );
+    });
+}
+
+#[test]
+fn fund_meeting_goal_and_min_contributors_is_dispensable() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PalletCrowdfund::create(
+            Origin::signed(1),
+            2,
+            1000,
+            10,
+            FundParams {
+                min_contributors: Some(3),
+                ..Default::default()
+            }
+        ));
+        for who in 3..=5 {
+            assert_ok!(PalletCrowdfund::contribute(
+                Origin::signed(who),
+                0,
+                400,
+                None
+            ));
+        }
+
+        System::set_block_number(10);
+        assert_ok!(PalletCrowdfund::dispense(Origin::signed(1), 0));
+        assert_eq!(Balances::free_balance(2), 1_001_200);
+    });
+}
+
+#[test]
+fn zero_contribution_is_rejected_explicitly() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_noop!(
+            PalletCrowdfund::contribute(Origin::signed(3), 0, 0, None),
+            Error::<Test>::ZeroContribution.with_weight(0),
+        );
+        assert_noop!(
+            PalletCrowdfund::contribute(Origin::signed(3), 0, 99, None),
+            Error::<Test>::ContributionTooSmall.with_weight(0),
+        );
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(3),
+            0,
+            MinContribution::get(),
+            None
+        ));
+    });
+}
+
+#[test]
+fn create_rejects_goal_below_minimum() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            create_fund(1, 2, MinGoal::get() - 1, 10),
+            Error::<Test>::GoalTooLow
+        );
+    });
+}
+
+#[test]
+fn creator_can_adjust_goal_before_contributions() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+
+        assert_noop!(
+            PalletCrowdfund::adjust_goal(Origin::signed(2), 0, 500),
+            Error::<Test>::NotFundCreator
+        );
+        assert_noop!(
+            PalletCrowdfund::adjust_goal(Origin::signed(1), 0, MinGoal::get() - 1),
+            Error::<Test>::GoalTooLow
+        );
+        // The goal can only go down
+        for goal in vec![1000, 1500] {
+            assert_noop!(
+                PalletCrowdfund::adjust_goal(Origin::signed(1), 0, goal),
+                Error::<Test>::GoalNotLowered
+            );
+        }
+
+        assert_ok!(PalletCrowdfund::adjust_goal(Origin::signed(1), 0, 500));
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().goal, 500);
+        assert_last_event(CrowdfundEvent::GoalAdjusted(0, 500));
+    });
+}
+
+#[test]
+fn adjust_goal_is_rejected_once_contributions_exist() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 100, None));
+
+        assert_noop!(
+            PalletCrowdfund::adjust_goal(Origin::signed(1), 0, 500),
+            Error::<Test>::ContributionsExist
+        );
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().goal, 1000);
+    });
+}
+
+#[test]
+fn raised_matches_fund_info() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(PalletCrowdfund::raised(0), 0);
+
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 500, None));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(4), 0, 300, None));
+
+        assert_eq!(PalletCrowdfund::raised(0), 800);
+        assert_eq!(
+            PalletCrowdfund::raised(0),
+            PalletCrowdfund::funds(0).unwrap().raised
+        );
+    });
+}
+
+#[test]
+fn failed_transfer_leaves_contribution_state_untouched() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 500, None));
+
+        // Account 3 cannot afford this on top of what it already gave
+        assert!(PalletCrowdfund::contribute(Origin::signed(3), 0, 1_000_000, None).is_err());
+        // Account 6 holds nothing at all
+        assert!(PalletCrowdfund::contribute(Origin::signed(6), 0, 100, None).is_err());
+
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().raised, 500);
+        assert_eq!(PalletCrowdfund::contribution_detail(0, &3), Some((1, 500)));
+        assert_eq!(PalletCrowdfund::contribution_detail(0, &6), None);
+        assert!(!PalletCrowdfund::has_contributed(0, &6));
+        assert_eq!(PalletCrowdfund::contributor_count(0), 1);
+        assert_accounting_invariant(0);
+    });
+}
+
+#[test]
+fn refund_limit_scales_with_per_entry_weight() {
+    new_test_ext().execute_with(|| {
+        // 10_000 base plus 1_000 per contributor
+        assert_eq!(PalletCrowdfund::refund_limit(9_000), 0);
+        assert_eq!(PalletCrowdfund::refund_limit(12_500), 2);
+
+        REFUND_ENTRY_WEIGHT.with(|w| *w.borrow_mut() = 2_500);
+        assert_eq!(PalletCrowdfund::refund_limit(12_500), 1);
+
+        // Never more than `MaxContributors`, however much weight is left
+        REFUND_ENTRY_WEIGHT.with(|w| *w.borrow_mut() = 1);
+        assert_eq!(
+            PalletCrowdfund::refund_limit(12_500),
+            MaxContributors::get()
+        );
+        REFUND_ENTRY_WEIGHT.with(|w| *w.borrow_mut() = 0);
+        assert_eq!(PalletCrowdfund::refund_limit(0), MaxContributors::get());
+    });
+}
+
+#[test]
+fn refund_all_without_limit_charges_a_full_batch_and_refunds_the_rest() {
+    new_test_ext().execute_with(|| {
+        let declared = crate::Call::<Test>::refund_all(0, None)
+            .get_dispatch_info()
+            .weight;
+        assert_eq!(declared, MockWeights::refund_all(MaxContributors::get()));
+
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        for who in 3..=5 {
+            assert_ok!(PalletCrowdfund::contribute(
+                Origin::signed(who),
+                0,
+                200,
+                None
+            ));
+        }
+
+        System::set_block_number(11);
+        let info = PalletCrowdfund::refund_all(Origin::signed(1), 0, None).unwrap();
+        assert_eq!(info.actual_weight, Some(MockWeights::refund_all(3)));
+        assert!(info.actual_weight.unwrap() < declared);
+        assert_last_event(CrowdfundEvent::BatchRefunded(0, 3, false));
+        assert_eq!(PalletCrowdfund::contributor_count(0), 0);
+    });
+}
+
+#[test]
+fn deposit_follows_the_calculator() {
+    new_test_ext().execute_with(|| {
+        DEPOSIT_SHARE.with(|share| *share.borrow_mut() = Some(Perbill::from_percent(10)));
+
+        assert_ok!(create_fund(1, 2, 50_000, 10));
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().deposit, 5_000);
+        assert_eq!(Balances::free_balance(1), 1_000_000 - 5_000);
+
+        // The calculator changing afterwards does not affect what was charged
+        DEPOSIT_SHARE.with(|share| *share.borrow_mut() = Some(Perbill::from_percent(50)));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(3),
+            0,
+            50_000,
+            None
+        ));
+
+        System::set_block_number(10);
+        assert_ok!(PalletCrowdfund::dispense(Origin::signed(1), 0));
+        assert_ok!(PalletCrowdfund::claim_deposit(Origin::signed(1), 0));
+        assert_eq!(Balances::free_balance(1), 1_000_000);
+    });
+}
+
+#[test]
+fn validate_create_matches_create() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(5);
+        let too_far = 5 + MaxDuration::get() + 1;
+        let cases = vec![
+            (1000, 5, Err(Error::<Test>::EndTooEarly.into())),
+            (1000, too_far, Err(Error::<Test>::EndTooFar.into())),
+            (
+                MinGoal::get() - 1,
+                10,
+                Err(Error::<Test>::GoalTooLow.into()),
+            ),
+            (1000, too_far - 1, Ok(())),
+        ];
+
+        for (goal, end, expected) in cases {
+            assert_eq!(PalletCrowdfund::validate_create(goal, end), expected);
+            assert_eq!(
+                create_fund(1, 2, goal, end)
+                    .map(|_| ())
+                    .map_err(|e| e.error),
+                expected
+            );
+        }
+        // Only the valid case created anything, and the dry runs changed nothing
+        assert_eq!(PalletCrowdfund::fund_count(), 1);
+    });
+}
+
+/// The `(contributor, amount, reason)` of every `ContributionRefunded` event so far.
+fn refunds() -> Vec<(u64, u64, RefundReason)> {
+    events()
+        .into_iter()
+        .filter_map(|event| match event {
+            CrowdfundEvent::ContributionRefunded(who, _, amount, reason, _) => {
+                Some((who, amount, reason))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn refunds_report_why_they_happened() {
+    new_test_ext().execute_with(|| {
+        for _ in 0..3 {
+            assert_ok!(create_fund(1, 2, 1000, 10));
+        }
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 100, None));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(4), 1, 200, None));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(5), 2, 300, None));
+
+        assert_ok!(PalletCrowdfund::report_and_slash(Origin::root(), 2));
+        System::set_block_number(11);
+        assert_ok!(PalletCrowdfund::withdraw(Origin::signed(3), 0));
+        assert_ok!(PalletCrowdfund::refund_all(Origin::signed(1), 1, None));
+
+        assert_eq!(
+            refunds(),
+            vec![
+                (5, 300, RefundReason::Slash),
+                (3, 100, RefundReason::Voluntary),
+                (4, 200, RefundReason::Sweep),
+            ]
+        );
+        // Voluntary withdrawals still emit the plain event too
+        assert!(events().contains(&CrowdfundEvent::Withdrew(3, 0, 100, 11)));
+    });
+}
+
+#[test]
+fn creations_are_rate_limited_per_account() {
+    new_test_ext().execute_with(|| {
+        CREATION_COOLDOWN.with(|cooldown| *cooldown.borrow_mut() = 5);
+
+        assert_ok!(create_fund(1, 2, 1000, 20));
+        assert_eq!(PalletCrowdfund::last_created(1), Some(1));
+        assert_noop!(create_fund(1, 2, 1000, 20), Error::<Test>::CreationTooSoon);
+        // Other accounts are unaffected
+        assert_ok!(create_fund(3, 2, 1000, 20));
+
+        System::set_block_number(5);
+        assert_noop!(create_fund(1, 2, 1000, 20), Error::<Test>::CreationTooSoon);
+        System::set_block_number(6);
+        assert_ok!(create_fund(1, 2, 1000, 20));
+        assert_eq!(PalletCrowdfund::last_created(1), Some(6));
+    });
+}
+
+/// Advance to block `n`, running the pallet's `on_initialize` for each new block.
+fn run_to_block(n: u64) {
+    while System::block_number() < n {
+        System::set_block_number(System::block_number() + 1);
+        PalletCrowdfund::on_initialize(System::block_number());
+    }
+}
+
+#[test]
+fn failed_auto_dispense_is_rolled_back_and_reported() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PalletCrowdfund::create(
+            Origin::signed(1),
+            2,
+            1000,
+            5,
+            FundParams {
+                splits: vec![(3, Perbill::from_percent(50))],
+                ..Default::default()
+            }
+        ));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(4),
+            0,
+            1000,
+            None
+        ));
+        // Enough for the split, but not for the beneficiary after it
+        let pot = PalletCrowdfund::fund_account_id(0);
+        Balances::make_free_balance_be(&pot, 700);
+
+        run_to_block(5);
+        assert_eq!(Balances::free_balance(3), 1_000_000);
+        assert_eq!(Balances::free_balance(pot), 700);
+        assert!(PalletCrowdfund::funds(0).is_some());
+        assert_last_event(CrowdfundEvent::AutoDispenseFailed(
+            0,
+            pallet_balances::Error::<Test, _>::InsufficientBalance.into(),
+        ));
+
+        // Until it is dispensed, the successful fund cannot be withdrawn from
+        run_to_block(6);
+        assert_noop!(
+            PalletCrowdfund::withdraw(Origin::signed(4), 0),
+            Error::<Test>::SuccessfulFund
+        );
+    });
+}
+
+#[test]
+fn successful_funds_are_dispensed_automatically_at_end() {
+    new_test_ext().execute_with(|| {
+        for (index, who) in (3..=5).enumerate() {
+            assert_ok!(create_fund(1, 2, 1000, 5));
+            assert_ok!(PalletCrowdfund::contribute(
+                Origin::signed(who),
+                index as u32,
+                1000,
+                None
+            ));
+        }
+        // Fund 3 fails and is left for refunds
+        assert_ok!(create_fund(1, 2, 1000, 5));
+
+        run_to_block(4);
+        assert!(PalletCrowdfund::funds(0).is_some());
+
+        // Only `MaxAutoDispense` funds are paid out in the block they end
+        run_to_block(5);
+        assert!(PalletCrowdfund::funds(0).is_none());
+        assert!(PalletCrowdfund::funds(1).is_none());
+        assert!(PalletCrowdfund::funds(2).is_some());
+        assert_eq!(Balances::free_balance(2), 1_000_000 + 2000);
+        assert!(events().contains(&CrowdfundEvent::Dispensed(0, 5, 1, 1000)));
+
+        // The rest are carried over to the next block
+        run_to_block(6);
+        assert!(PalletCrowdfund::funds(2).is_none());
+        assert!(PalletCrowdfund::funds(3).is_some());
+        assert!(crate::DispenseQueue::<Test>::get().is_empty());
+        assert_eq!(Balances::free_balance(2), 1_000_000 + 3000);
+        // The creator can take back the deposit of every dispensed fund
+        for index in 0..3 {
+            assert_ok!(PalletCrowdfund::claim_deposit(Origin::signed(1), index));
+        }
+        assert_eq!(
+            Balances::free_balance(1),
+            1_000_000 - SubmissionDeposit::get()
+        );
+    });
+}
+
+#[test]
+fn withdraw_closes_when_retirement_period_ends() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 500, None));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(4), 0, 300, None));
+
+        // The last block before the fund can be dissolved
+        System::set_block_number(19);
+        assert_ok!(PalletCrowdfund::withdraw(Origin::signed(3), 0));
+
+        System::set_block_number(20);
+        assert_noop!(
+            PalletCrowdfund::withdraw(Origin::signed(4), 0),
+            Error::<Test>::RefundPeriodExpired
+        );
+
+        // The dissolver takes what was not withdrawn in time
+        assert_ok!(PalletCrowdfund::dissolve(Origin::signed(5), 0));
+        assert_eq!(Balances::free_balance(5), 1_000_000 + 10_000 + 300);
+        assert_eq!(Balances::free_balance(4), 1_000_000 - 300);
+    });
+}
+
+#[test]
+fn creator_can_change_beneficiary_before_contributions() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+
+        assert_noop!(
+            PalletCrowdfund::set_beneficiary(Origin::signed(2), 0, 4),
+            Error::<Test>::NotFundCreator
+        );
+
+        assert_ok!(PalletCrowdfund::set_beneficiary(Origin::signed(1), 0, 4));
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().beneficiary, 4);
+        assert_last_event(CrowdfundEvent::BeneficiaryChanged(0, 2, 4));
+    });
+}
+
+#[test]
+fn beneficiary_is_fixed_once_contributions_exist_or_fund_ends() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 100, None));
+
+        assert_noop!(
+            PalletCrowdfund::set_beneficiary(Origin::signed(1), 0, 4),
+            Error::<Test>::ContributionsExist
+        );
+
+        System::set_block_number(10);
+        assert_noop!(
+            PalletCrowdfund::set_beneficiary(Origin::signed(1), 1, 4),
+            Error::<Test>::ContributionPeriodOver
+        );
+    });
+}
+
+#[test]
+fn contribution_fee_counts_towards_raised_but_is_not_refunded() {
+    new_test_ext().execute_with(|| {
+        CONTRIBUTION_FEE.with(|fee| *fee.borrow_mut() = Perbill::from_percent(2));
+        assert_ok!(create_fund(1, 2, 10_000, 10));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(3),
+            0,
+            1000,
+            None
+        ));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(4), 0, 550, None));
+
+        let fund = PalletCrowdfund::funds(0).unwrap();
+        assert_eq!(fund.raised, 1550);
+        // Fees round down in the contributor's favour: 2% of 550 is 11
+        assert_eq!(fund.fees, 20 + 11);
+        assert_eq!(PalletCrowdfund::contribution_detail(0, &3), Some((1, 980)));
+        assert_eq!(PalletCrowdfund::contribution_detail(0, &4), Some((1, 539)));
+        assert_accounting_invariant(0);
+
+        // The fund fails, and only the refundable part comes back
+        System::set_block_number(11);
+        assert_ok!(PalletCrowdfund::withdraw(Origin::signed(3), 0));
+        assert_ok!(PalletCrowdfund::withdraw(Origin::signed(4), 0));
+        assert_eq!(Balances::free_balance(3), 1_000_000 - 20);
+        assert_eq!(Balances::free_balance(4), 1_000_000 - 11);
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().raised, 31);
+        assert_accounting_invariant(0);
+    });
+}
+
+#[test]
+fn contribution_overflowing_raised_is_rejected() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        crate::Funds::<Test>::mutate(0, |fund| {
+            fund.as_mut().unwrap().raised = u64::max_value() - 50
+        });
+
+        assert_noop!(
+            PalletCrowdfund::contribute(Origin::signed(3), 0, 100, None),
+            Error::<Test>::Overflow
+        );
+        assert_eq!(Balances::free_balance(3), 1_000_000);
+        assert_eq!(
+            PalletCrowdfund::funds(0).unwrap().raised,
+            u64::max_value() - 50
+        );
+    });
+}
+
+#[test]
+fn funds_can_be_enumerated_by_category() {
+    new_test_ext().execute_with(|| {
+        let create_in = |category| {
+            PalletCrowdfund::create(
+                Origin::signed(1),
+                2,
+                1000,
+                10,
+                FundParams {
+                    category,
+                    ..Default::default()
+                },
+            )
+        };
+        assert_ok!(create_in(Category::Art));
+        assert_ok!(create_in(Category::Charity));
+        assert_ok!(create_in(Category::Art));
+        assert_eq!(
+            PalletCrowdfund::funds(1).unwrap().category,
+            Category::Charity
+        );
+
+        let sorted = |category| {
+            let mut funds = PalletCrowdfund::funds_in_category(category);
+            funds.sort();
+            funds
+        };
+        assert_eq!(sorted(Category::Art), vec![0, 2]);
+        assert_eq!(sorted(Category::Charity), vec![1]);
+        assert!(sorted(Category::Tech).is_empty());
+
+        // Removed funds leave the index
+        assert_ok!(PalletCrowdfund::report_and_slash(Origin::root(), 0));
+        assert_eq!(sorted(Category::Art), vec![2]);
+    });
+}
+
+#[test]
+fn creator_claims_deposit_after_dispense() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(3),
+            0,
+            1000,
+            None
+        ));
+        assert_noop!(
+            PalletCrowdfund::claim_deposit(Origin::signed(1), 0),
+            Error::<Test>::NoDeposit
+        );
+
+        // Anyone may dispense without taking the deposit
+        System::set_block_number(10);
+        assert_ok!(PalletCrowdfund::dispense(Origin::signed(4), 0));
+        assert_eq!(Balances::free_balance(4), 1_000_000);
+        assert_eq!(PalletCrowdfund::unclaimed_deposit(0), Some((1, 10_000)));
+
+        assert_noop!(
+            PalletCrowdfund::claim_deposit(Origin::signed(4), 0),
+            Error::<Test>::NotFundCreator
+        );
+        assert_ok!(PalletCrowdfund::claim_deposit(Origin::signed(1), 0));
+        assert_eq!(Balances::free_balance(1), 1_000_000);
+        assert_last_event(CrowdfundEvent::DepositClaimed(0, 1, 10_000));
+
+        assert_noop!(
+            PalletCrowdfund::claim_deposit(Origin::signed(1), 0),
+            Error::<Test>::NoDeposit
+        );
+    });
+}
+
+#[test]
+fn pot_account_is_stable_and_emptied_by_payouts() {
+    new_test_ext().execute_with(|| {
+        // The pot is the fund's sub-account of the pallet's module ID
+        let expected: u64 = ModuleId(*b"ex/cfund").into_sub_account(0u32);
+        assert_eq!(PalletCrowdfund::fund_account_id(0), expected);
+        assert_ne!(
+            PalletCrowdfund::fund_account_id(0),
+            PalletCrowdfund::fund_account_id(1)
+        );
+
+        assert_ok!(PalletCrowdfund::create(
+            Origin::signed(1),
+            2,
+            1000,
+            10,
+            FundParams {
+                splits: vec![(3, Perbill::from_percent(25))],
+                ..Default::default()
+            }
+        ));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(4),
+            0,
+            1000,
+            None
+        ));
+        let pot = PalletCrowdfund::fund_account_id(0);
+        assert_eq!(Balances::free_balance(pot), 11_000);
+
+        System::set_block_number(10);
+        assert_ok!(PalletCrowdfund::dispense(Origin::signed(5), 0));
+        assert_eq!(Balances::free_balance(3), 1_000_250);
+        assert_eq!(Balances::free_balance(2), 1_000_750);
+        assert_eq!(Balances::free_balance(pot), 10_000);
+
+        assert_ok!(PalletCrowdfund::claim_deposit(Origin::signed(1), 0));
+        assert_eq!(Balances::free_balance(pot), 0);
+    });
+}
+
+#[test]
+fn calls_on_missing_fund_report_invalid_index() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        System::set_block_number(100);
+
+        let calls: Vec<DispatchResultWithPostInfo> = vec![
+            PalletCrowdfund::contribute(Origin::signed(3), 1, 100, None),
+            PalletCrowdfund::withdraw(Origin::signed(3), 1),
+            PalletCrowdfund::dispense(Origin::signed(3), 1),
+            PalletCrowdfund::dissolve(Origin::signed(3), 1),
+            PalletCrowdfund::adjust_goal(Origin::signed(1), 1, 500),
+            PalletCrowdfund::set_beneficiary(Origin::signed(1), 1, 4),
+            PalletCrowdfund::refund_all(Origin::signed(3), 1, None),
+            PalletCrowdfund::report_and_slash(Origin::root(), 1),
+        ];
+        for result in calls {
+            assert_eq!(
+                result.map_err(|e| e.error),
+                Err(Error::<Test>::InvalidIndex.into())
+            );
+        }
+    });
+}
+
+#[test]
+fn rejected_contributions_refund_unused_weight() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        let actual_weight =
+            |result: DispatchResultWithPostInfo| result.unwrap_err().post_info.actual_weight;
+
+        assert_eq!(
+            actual_weight(PalletCrowdfund::contribute(Origin::signed(3), 0, 0, None)),
+            Some(0)
+        );
+        assert_eq!(
+            actual_weight(PalletCrowdfund::contribute(Origin::signed(3), 0, 99, None)),
+            Some(0)
+        );
+        assert_eq!(
+            actual_weight(PalletCrowdfund::contribute(Origin::signed(3), 1, 100, None)),
+            Some(<Test as frame_system::Config>::DbWeight::get().reads(1))
+        );
+        // Later rejections did real work and are charged in full
+        System::set_block_number(10);
+        assert_eq!(
+            actual_weight(PalletCrowdfund::contribute(Origin::signed(3), 0, 100, None)),
+            None
+        );
+    });
+}
+
+/// Create a failed fund holding 500 of contributions and dissolve it once retired.
+fn dissolve_failed_fund() {
+    assert_ok!(create_fund(1, 2, 1000, 10));
+    assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 500, None));
+    System::set_block_number(20);
+    assert_ok!(PalletCrowdfund::dissolve(Origin::signed(4), 0));
+}
+
+#[test]
+fn dissolve_can_burn_the_residue() {
+    new_test_ext().execute_with(|| {
+        RESIDUE_POLICY.with(|policy| *policy.borrow_mut() = ResiduePolicy::Burn);
+        let issuance = Balances::total_issuance();
+
+        dissolve_failed_fund();
+
+        assert_eq!(Balances::free_balance(4), 1_000_000);
+        assert_eq!(Balances::total_issuance(), issuance - 10_500);
+        assert_last_event(CrowdfundEvent::Dissolved(0, 20, 4, 10_500));
+    });
+}
+
+#[test]
+fn dissolve_can_send_the_residue_to_treasury() {
+    new_test_ext().execute_with(|| {
+        RESIDUE_POLICY.with(|policy| *policy.borrow_mut() = ResiduePolicy::Treasury);
+        let issuance = Balances::total_issuance();
+
+        dissolve_failed_fund();
+
+        assert_eq!(Balances::free_balance(4), 1_000_000);
+        assert_eq!(Balances::free_balance(TREASURY), 10_500);
+        assert_eq!(Balances::total_issuance(), issuance);
+    });
+}
+
+#[test]
+fn beneficiary_cannot_be_the_fund_pot() {
+    new_test_ext().execute_with(|| {
+        let pot = PalletCrowdfund::fund_account_id(0);
+        assert_noop!(
+            create_fund(1, pot, 1000, 10),
+            Error::<Test>::InvalidBeneficiary
+        );
+
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_noop!(
+            PalletCrowdfund::set_beneficiary(Origin::signed(1), 0, pot),
+            Error::<Test>::InvalidBeneficiary
+        );
+        // Another fund's pot is just an account
+        assert_ok!(create_fund(1, pot, 1000, 10));
+    });
+}
+
+#[test]
+fn creator_as_beneficiary_nets_out_payout_and_deposit() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 1, 1000, 10));
+        assert_eq!(Balances::free_balance(1), 1_000_000 - 10_000);
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 600, None));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(4), 0, 400, None));
+
+        System::set_block_number(10);
+        assert_ok!(PalletCrowdfund::dispense(Origin::signed(5), 0));
+        assert_eq!(Balances::free_balance(1), 1_000_000 - 10_000 + 1000);
+
+        assert_ok!(PalletCrowdfund::claim_deposit(Origin::signed(1), 0));
+        assert_eq!(Balances::free_balance(1), 1_001_000);
+        assert_eq!(
+            Balances::free_balance(PalletCrowdfund::fund_account_id(0)),
+            0
+        );
+    });
+}
+
+#[test]
+fn blocks_remaining_match_the_call_guards() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(PalletCrowdfund::blocks_until_end(0), None);
+        assert_eq!(PalletCrowdfund::blocks_until_dissolvable(0), None);
+        assert_ok!(create_fund(1, 2, 1000, 10));
+
+        assert_eq!(PalletCrowdfund::blocks_until_end(0), Some(9));
+        assert_eq!(PalletCrowdfund::blocks_until_dissolvable(0), Some(19));
+
+        // The last block contributions are accepted
+        System::set_block_number(9);
+        assert_eq!(PalletCrowdfund::blocks_until_end(0), Some(1));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 100, None));
+
+        System::set_block_number(10);
+        assert_eq!(PalletCrowdfund::blocks_until_end(0), Some(0));
+        assert_noop!(
+            PalletCrowdfund::contribute(Origin::signed(3), 0, 100, None),
+            Error::<Test>::ContributionPeriodOver
+        );
+
+        System::set_block_number(19);
+        assert_eq!(PalletCrowdfund::blocks_until_dissolvable(0), Some(1));
+        assert_noop!(
+            PalletCrowdfund::dissolve(Origin::signed(4), 0),
+            Error::<Test>::FundNotRetired
+        );
+
+        System::set_block_number(25);
+        assert_eq!(PalletCrowdfund::blocks_until_end(0), Some(0));
+        assert_eq!(PalletCrowdfund::blocks_until_dissolvable(0), Some(0));
+        assert_noop!(
+            PalletCrowdfund::withdraw(Origin::signed(3), 0),
+            Error::<Test>::RefundPeriodExpired
+        );
+        assert_ok!(PalletCrowdfund::dissolve(Origin::signed(4), 0));
+    });
+}
+
+#[test]
+fn private_contribution_is_withdrawn_with_its_secret() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        let secret = b"correct horse".to_vec();
+        // Account 4 pays in, but the contribution belongs to whoever can prove account 5's
+        // commitment
+        let commitment = BlakeTwo256::hash_of(&(5u64, secret.clone()));
+        assert_ok!(PalletCrowdfund::contribute_private(
+            Origin::signed(4),
+            0,
+            300,
+            commitment,
+            None
+        ));
+
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().raised, 300);
+        assert_eq!(
+            PalletCrowdfund::private_contribution_get(0, &commitment),
+            300
+        );
+        assert!(!PalletCrowdfund::has_contributed(0, &4));
+        assert_last_event(CrowdfundEvent::ContributedPrivately(0, commitment, 300, 1));
+
+        System::set_block_number(11);
+        // The wrong secret, or the right secret from another account, finds nothing
+        assert_noop!(
+            PalletCrowdfund::withdraw_private(Origin::signed(5), 0, b"wrong".to_vec()),
+            Error::<Test>::NoContribution
+        );
+        assert_noop!(
+            PalletCrowdfund::withdraw_private(Origin::signed(3), 0, secret.clone()),
+            Error::<Test>::NoContribution
+        );
+
+        assert_ok!(PalletCrowdfund::withdraw_private(
+            Origin::signed(5),
+            0,
+            secret
+        ));
+        assert_eq!(Balances::free_balance(5), 1_000_300);
+        assert_eq!(Balances::free_balance(4), 1_000_000 - 300);
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().raised, 0);
+        assert_eq!(PalletCrowdfund::private_contribution_get(0, &commitment), 0);
+    });
+}
+
+#[test]
+fn private_contributions_follow_the_rules_of_public_ones() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        let commitment = |who: u64| BlakeTwo256::hash_of(&(who, b"secret".to_vec()));
+        for who in 3..6 {
+            assert_ok!(PalletCrowdfund::contribute(
+                Origin::signed(who),
+                0,
+                100,
+                None
+            ));
+        }
+        assert_ok!(PalletCrowdfund::contribute_private(
+            Origin::signed(6),
+            0,
+            100,
+            commitment(6),
+            Some(7)
+        ));
+        assert_eq!(PalletCrowdfund::contributor_count(0), 4);
+
+        // A resubmission is caught by its client reference, and a new commitment is one
+        // contributor too many
+        assert_noop!(
+            PalletCrowdfund::contribute_private(Origin::signed(6), 0, 100, commitment(6), Some(7)),
+            Error::<Test>::DuplicateContribution
+        );
+        assert_noop!(
+            PalletCrowdfund::contribute_private(Origin::signed(7), 0, 100, commitment(7), None),
+            Error::<Test>::TooManyContributors
+        );
+
+        // Withdrawing frees the slot, and an undecodable entry is an error rather than nothing
+        System::set_block_number(11);
+        assert_ok!(PalletCrowdfund::withdraw_private(
+            Origin::signed(6),
+            0,
+            b"secret".to_vec()
+        ));
+        assert_eq!(PalletCrowdfund::contributor_count(0), 3);
+        child::put_raw(
+            &PalletCrowdfund::private_id_from_index(0),
+            &commitment(7).encode(),
+            &[1, 2, 3],
+        );
+        assert_noop!(
+            PalletCrowdfund::withdraw_private(Origin::signed(7), 0, b"secret".to_vec()),
+            Error::<Test>::CorruptContribution
+        );
+    });
+}
+
+#[test]
+fn create_emits_created_with_each_new_index() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        System::set_block_number(3);
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_eq!(
+            events(),
+            vec![CrowdfundEvent::Created(0, 1), CrowdfundEvent::Created(1, 3)]
+        );
+    });
+}
+
+#[test]
+fn contribute_emits_running_total_and_whether_contributor_is_new() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 200, None));
+        assert_last_event(CrowdfundEvent::Contributed(3, 0, 200, 1, true));
+
+        System::set_block_number(2);
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 150, None));
+        assert_last_event(CrowdfundEvent::Contributed(3, 0, 350, 2, false));
+    });
+}
+
+#[test]
+fn withdraw_emits_withdrew_then_refund() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 500, None));
+        System::set_block_number(11);
+        assert_ok!(PalletCrowdfund::withdraw(Origin::signed(3), 0));
+
+        let events = events();
+        assert_eq!(
+            events[events.len() - 2..],
+            [
+                CrowdfundEvent::Withdrew(3, 0, 500, 11),
+                CrowdfundEvent::ContributionRefunded(3, 0, 500, RefundReason::Voluntary, 11),
+            ]
+        );
+    });
+}
+
+#[test]
+fn mock_runtime_mounts_the_pallet_with_its_config() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(System::block_number(), 1);
+        for who in 1..=5 {
+            assert_eq!(Balances::free_balance(who), 1_000_000);
+        }
+        assert_eq!(PalletCrowdfund::fund_count(), 0);
+
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_eq!(
+            Balances::free_balance(1),
+            1_000_000 - <SubmissionDeposit as Get<u64>>::get()
+        );
+        assert_noop!(
+            PalletCrowdfund::contribute(
+                Origin::signed(3),
+                0,
+                <MinContribution as Get<u64>>::get() - 1,
+                None
+            ),
+            Error::<Test>::ContributionTooSmall.with_weight(0)
+        );
+    });
+}
+
+fn create_fund_with_min_contribution(min: Option<u64>) -> DispatchResultWithPostInfo {
+    PalletCrowdfund::create(
+        Origin::signed(1),
+        2,
+        1000,
+        10,
+        FundParams {
+            min_contribution: min,
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+fn fund_minimum_rejects_contributions_the_global_floor_allows() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund_with_min_contribution(Some(250)));
+        assert_eq!(
+            PalletCrowdfund::funds(0).unwrap().min_contribution,
+            Some(250)
+        );
+
+        // 100 clears the global MinContribution but not the fund's own minimum
+        assert_noop!(
+            PalletCrowdfund::contribute(Origin::signed(3), 0, 100, None),
+            Error::<Test>::ContributionBelowFundMinimum
+        );
+        assert_noop!(
+            PalletCrowdfund::contribute_private(
+                Origin::signed(3),
+                0,
+                100,
+                Default::default(),
+                None
+            ),
+            Error::<Test>::ContributionBelowFundMinimum
+        );
+        // The global floor is still reported as such
+        assert_noop!(
+            PalletCrowdfund::contribute(Origin::signed(3), 0, 99, None),
+            Error::<Test>::ContributionTooSmall.with_weight(0)
+        );
+
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 250, None));
+        assert_eq!(PalletCrowdfund::contribution_get(0, &3), 250);
+    });
+}
+
+#[test]
+fn fund_minimum_cannot_undercut_the_global_floor() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            create_fund_with_min_contribution(Some(99)),
+            Error::<Test>::FundMinimumTooLow
+        );
+        assert_ok!(create_fund_with_min_contribution(Some(100)));
+        assert_ok!(create_fund_with_min_contribution(None));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 1, 100, None));
+    });
+}
+
+#[test]
+fn match_adds_its_share_of_each_contribution_up_to_the_cap() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::add_match(
+            Origin::signed(4),
+            0,
+            Perbill::from_percent(50),
+            300
+        ));
+        assert_eq!(Balances::reserved_balance(4), 300);
+        assert_noop!(
+            PalletCrowdfund::add_match(Origin::signed(5), 0, Perbill::from_percent(10), 100),
+            Error::<Test>::MatchExists
+        );
+
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 200, None));
+        assert_last_event(CrowdfundEvent::Matched(0, 3, 100));
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().raised, 300);
+        assert_eq!(PalletCrowdfund::match_pool(0).unwrap().remaining, 200);
+
+        // Half of 500 would be 250, but only 200 of the cap is left
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(5), 0, 500, None));
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().raised, 1000);
+        assert_eq!(PalletCrowdfund::match_pool(0).unwrap().remaining, 0);
+        // Contributors are only owed what they put in
+        assert_eq!(PalletCrowdfund::contribution_get(0, &5), 500);
+
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 100, None));
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().raised, 1100);
+        assert_eq!(Balances::reserved_balance(4), 0);
+        assert_eq!(Balances::free_balance(4), 1_000_000 - 300);
+    });
+}
+
+#[test]
+fn sponsor_is_only_returned_what_was_drawn() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::add_match(
+            Origin::signed(4),
+            0,
+            Perbill::from_percent(50),
+            300
+        ));
+        // Most of the sponsor's reserve is slashed elsewhere, so only 50 of the 100 matched
+        // reaches the pot
+        let _ = Balances::slash_reserved(&4, 250);
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 200, None));
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().raised, 250);
+        assert_eq!(PalletCrowdfund::match_pool(0).unwrap().drawn, 50);
+
+        System::set_block_number(11);
+        assert_ok!(PalletCrowdfund::release_match(Origin::signed(4), 0));
+        assert_eq!(Balances::free_balance(4), 1_000_000 - 250);
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().raised, 200);
+        assert_eq!(
+            Balances::free_balance(PalletCrowdfund::fund_account_id(0)),
+            10_000 + 200
+        );
+    });
+}
+
+#[test]
+fn dispense_returns_undrawn_match_to_sponsor() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::add_match(
+            Origin::signed(4),
+            0,
+            Perbill::from_percent(10),
+            500
+        ));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(3),
+            0,
+            1000,
+            None
+        ));
+
+        System::set_block_number(11);
+        assert_ok!(PalletCrowdfund::dispense(Origin::signed(1), 0));
+        assert_eq!(Balances::free_balance(2), 1_000_000 + 1100);
+        assert_eq!(Balances::reserved_balance(4), 0);
+        assert_eq!(Balances::free_balance(4), 1_000_000 - 100);
+        assert!(PalletCrowdfund::match_pool(0).is_none());
+    });
+}
+
+#[test]
+fn failed_fund_returns_all_matching_funds_to_sponsor() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::add_match(
+            Origin::signed(4),
+            0,
+            Perbill::from_percent(50),
+            300
+        ));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 200, None));
+        assert_noop!(
+            PalletCrowdfund::release_match(Origin::signed(4), 0),
+            Error::<Test>::FundStillActive
+        );
+
+        System::set_block_number(11);
+        assert_ok!(PalletCrowdfund::release_match(Origin::signed(5), 0));
+        assert_last_event(CrowdfundEvent::MatchReleased(0, 4, 300));
+        assert_eq!(Balances::free_balance(4), 1_000_000);
+        assert_eq!(Balances::reserved_balance(4), 0);
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().raised, 200);
+        assert_noop!(
+            PalletCrowdfund::release_match(Origin::signed(4), 0),
+            Error::<Test>::NoMatch
+        );
+
+        // The contributor still gets their own contribution back
+        assert_ok!(PalletCrowdfund::withdraw(Origin::signed(3), 0));
+        assert_eq!(Balances::free_balance(3), 1_000_000);
+    });
+}
+
+#[test]
+fn corrupt_contribution_is_an_error_not_a_zero_refund() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 500, None));
+        assert_eq!(PalletCrowdfund::contribution_try_get(0, &3), Ok(Some(500)));
+        assert_eq!(PalletCrowdfund::contribution_try_get(0, &4), Ok(None));
+
+        // Too short to decode as a balance
+        child::put_raw(
+            &PalletCrowdfund::id_from_index(0),
+            &3u64.encode(),
+            &[1, 2, 3],
+        );
+        assert_eq!(PalletCrowdfund::contribution_get(0, &3), 0);
+        assert_eq!(
+            PalletCrowdfund::contribution_try_get(0, &3),
+            Err(Error::<Test>::CorruptContribution.into())
+        );
+
+        System::set_block_number(11);
+        assert_noop!(
+            PalletCrowdfund::withdraw(Origin::signed(3), 0),
+            Error::<Test>::CorruptContribution
+        );
+        assert_noop!(
+            PalletCrowdfund::refund_all(Origin::signed(1), 0, None),
+            Error::<Test>::CorruptContribution
+        );
+        assert_noop!(
+            PalletCrowdfund::report_and_slash(Origin::root(), 0),
+            Error::<Test>::CorruptContribution
+        );
+    });
+}
+
+#[test]
+fn create_with_duration_matches_absolute_end() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(5);
+        assert_ok!(PalletCrowdfund::create_with_duration(
+            Origin::signed(1),
+            2,
+            1000,
+            20
+        ));
+        assert_ok!(create_fund(1, 2, 1000, 25));
+
+        let by_duration = PalletCrowdfund::funds(0).unwrap();
+        let by_end = PalletCrowdfund::funds(1).unwrap();
+        assert_eq!(by_duration.end, 25);
+        assert_eq!(by_duration, by_end);
+        assert_eq!(PalletCrowdfund::blocks_until_end(0), Some(20));
+    });
+}
+
+#[test]
+fn create_with_duration_rejects_zero_and_excessive_durations() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            PalletCrowdfund::create_with_duration(Origin::signed(1), 2, 1000, 0),
+            Error::<Test>::EndTooEarly
+        );
+        let max = <MaxDuration as Get<u64>>::get();
+        assert_noop!(
+            PalletCrowdfund::create_with_duration(Origin::signed(1), 2, 1000, max + 1),
+            Error::<Test>::EndTooFar
+        );
+        assert_noop!(
+            PalletCrowdfund::create_with_duration(Origin::signed(1), 2, 1000, u64::max_value()),
+            Error::<Test>::EndTooFar
+        );
+        assert_ok!(PalletCrowdfund::create_with_duration(
+            Origin::signed(1),
+            2,
+            1000,
+            max
+        ));
+    });
+}
+
+#[test]
+fn frozen_fund_pays_nothing_out_until_unfrozen() {
+    new_test_ext().execute_with(|| {
+        // Fund 0 fails and fund 1 succeeds
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 500, None));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(3),
+            1,
+            1000,
+            None
+        ));
+
+        assert_noop!(
+            PalletCrowdfund::set_fund_frozen(Origin::signed(1), 0, true),
+            DispatchError::BadOrigin
+        );
+        assert_ok!(PalletCrowdfund::set_fund_frozen(Origin::root(), 0, true));
+        assert_ok!(PalletCrowdfund::set_fund_frozen(Origin::root(), 1, true));
+        assert_last_event(CrowdfundEvent::FrozenChanged(1, true));
+        assert_noop!(
+            PalletCrowdfund::contribute(Origin::signed(4), 0, 100, None),
+            Error::<Test>::FundFrozen
+        );
+
+        // Frozen funds are held back from automatic dispensing
+        run_to_block(11);
+        assert!(PalletCrowdfund::funds(1).is_some());
+        assert_noop!(
+            PalletCrowdfund::withdraw(Origin::signed(3), 0),
+            Error::<Test>::FundFrozen
+        );
+        assert_noop!(
+            PalletCrowdfund::refund_all(Origin::signed(1), 0, None),
+            Error::<Test>::FundFrozen
+        );
+        assert_noop!(
+            PalletCrowdfund::dispense(Origin::signed(1), 1),
+            Error::<Test>::FundFrozen
+        );
+
+        System::set_block_number(20);
+        assert_noop!(
+            PalletCrowdfund::dissolve(Origin::signed(1), 0),
+            Error::<Test>::FundFrozen
+        );
+
+        assert_ok!(PalletCrowdfund::set_fund_frozen(Origin::root(), 0, false));
+        assert_ok!(PalletCrowdfund::set_fund_frozen(Origin::root(), 1, false));
+        assert!(!PalletCrowdfund::is_frozen(0));
+        assert_ok!(PalletCrowdfund::dissolve(Origin::signed(1), 0));
+
+        // The successful fund stayed queued, so it is dispensed automatically once unfrozen
+        run_to_block(21);
+        assert!(PalletCrowdfund::funds(1).is_none());
+        assert!(events().contains(&CrowdfundEvent::Dispensed(1, 21, 1, 1000)));
+    });
+}
+
+#[test]
+fn unfrozen_fund_can_be_withdrawn_from() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 500, None));
+        assert_ok!(PalletCrowdfund::set_fund_frozen(Origin::root(), 0, true));
+        assert_ok!(PalletCrowdfund::set_fund_frozen(Origin::root(), 0, false));
+
+        System::set_block_number(11);
+        assert_ok!(PalletCrowdfund::withdraw(Origin::signed(3), 0));
+        assert_eq!(Balances::free_balance(3), 1_000_000);
+    });
+}
+
+#[test]
+fn frozen_fund_accepts_contributions_when_configured() {
+    new_test_ext().execute_with(|| {
+        FROZEN_CONTRIBUTIONS.with(|allowed| *allowed.borrow_mut() = true);
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::set_fund_frozen(Origin::root(), 0, true));
+
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 500, None));
+        assert_eq!(PalletCrowdfund::contribution_get(0, &3), 500);
+    });
+}
+
+#[test]
+fn dispense_sweeps_dust_so_the_pot_ends_empty() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(3),
+            0,
+            1000,
+            None
+        ));
+        let pot = PalletCrowdfund::fund_account_id(0);
+        // Dust nobody accounts for, sent straight to the pot
+        assert_ok!(Balances::transfer(Origin::signed(4), pot, 7));
+
+        System::set_block_number(11);
+        assert_ok!(PalletCrowdfund::dispense(Origin::signed(5), 0));
+        assert!(events().contains(&CrowdfundEvent::DustSwept(0, 1, 7)));
+        assert_eq!(Balances::free_balance(2), 1_000_000 + 1000);
+        assert_eq!(Balances::free_balance(1), 1_000_000 - 10_000 + 7);
+        // Only the unclaimed deposit is left
+        assert_eq!(Balances::free_balance(pot), 10_000);
+
+        assert_ok!(PalletCrowdfund::claim_deposit(Origin::signed(1), 0));
+        assert_eq!(Balances::free_balance(1), 1_000_000 + 7);
+        assert_eq!(Balances::total_balance(&pot), 0);
+    });
+}
+
+#[test]
+fn quiet_fund_emits_one_summary_per_block_instead_of_each_contribution() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PalletCrowdfund::create(
+            Origin::signed(1),
+            2,
+            1000,
+            10,
+            FundParams {
+                emit_contributions: false,
+                ..Default::default()
+            }
+        ));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 200, None));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(4), 0, 300, None));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 100, None));
+        assert!(!events()
+            .iter()
+            .any(|event| matches!(event, CrowdfundEvent::Contributed(..))));
+
+        PalletCrowdfund::on_finalize(1);
+        assert_last_event(CrowdfundEvent::ContributionSummary(0, 600, 2, 1));
+
+        // Nothing is summarised for a block without contributions
+        let before = events().len();
+        PalletCrowdfund::on_finalize(2);
+        assert_eq!(events().len(), before);
+    });
+}
+
+#[test]
+fn default_fund_emits_each_contribution_and_no_summary() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 200, None));
+        PalletCrowdfund::on_finalize(1);
+        assert_last_event(CrowdfundEvent::Contributed(3, 0, 200, 1, true));
+    });
+}
+
+#[test]
+fn create_rejects_deposit_below_existential_deposit() {
+    new_test_ext().execute_with(|| {
+        // 0.1% of the goal: 0 for a goal of 100, exactly the existential deposit for 1000
+        DEPOSIT_SHARE.with(|share| *share.borrow_mut() = Some(Perbill::from_perthousand(1)));
+        assert_eq!(<ExistentialDeposit as Get<u64>>::get(), 1);
+
+        assert_noop!(create_fund(1, 2, 100, 10), Error::<Test>::DepositTooLow);
+        assert_eq!(
+            PalletCrowdfund::validate_create(100, 10),
+            Err(Error::<Test>::DepositTooLow.into())
+        );
+        assert_eq!(PalletCrowdfund::validate_create(1000, 10), Ok(()));
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_eq!(PalletCrowdfund::funds(0).unwrap().deposit, 1);
+        assert_eq!(
+            Balances::free_balance(PalletCrowdfund::fund_account_id(0)),
+            1
+        );
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 100, None));
+    });
+}
+
+#[test]
+fn create_and_contribute_seeds_the_new_fund() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(create_fund(2, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::create_and_contribute(
+            Origin::signed(1),
+            2,
+            1000,
+            10,
+            300
+        ));
+
+        assert_eq!(
+            events()[events().len() - 2..],
+            [
+                CrowdfundEvent::Created(1, 1),
+                CrowdfundEvent::Contributed(1, 1, 300, 1, true),
+            ]
+        );
+        assert_eq!(PalletCrowdfund::funds(1).unwrap().raised, 300);
+        assert_eq!(PalletCrowdfund::contribution_get(1, &1), 300);
+        assert_eq!(PalletCrowdfund::contributor_count(1), 1);
+        assert_eq!(Balances::free_balance(1), 1_000_000 - 10_000 - 300);
+    });
+}
+
+#[test]
+fn create_and_contribute_creates_nothing_if_the_seed_is_rejected() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            PalletCrowdfund::create_and_contribute(Origin::signed(1), 2, 1000, 10, 99),
+            Error::<Test>::ContributionTooSmall
+        );
+        // The deposit is affordable but the seed on top of it is not
+        assert_noop!(
+            PalletCrowdfund::create_and_contribute(Origin::signed(1), 2, 1000, 10, 995_000),
+            pallet_balances::Error::<Test, _>::InsufficientBalance
+        );
+        assert_eq!(PalletCrowdfund::fund_count(), 0);
+    });
+}
+
+#[test]
+fn verify_child_info_accepts_only_the_fund_contributions_trie() {
+    new_test_ext().execute_with(|| {
+        let info = PalletCrowdfund::id_from_index(3);
+        assert!(PalletCrowdfund::verify_child_info(3, &info));
+        assert!(!PalletCrowdfund::verify_child_info(4, &info));
+        assert!(!PalletCrowdfund::verify_child_info(
+            3,
+            &PalletCrowdfund::detail_id_from_index(3)
+        ));
+
+        assert_eq!(
+            PalletCrowdfund::child_trie_key(3),
+            info.prefixed_storage_key().into_inner()
+        );
+    });
+}
+
+#[test]
+fn removing_a_fund_clears_its_client_refs() {
+    new_test_ext().execute_with(|| {
+        // Fund 0 is dispensed, fund 1 dissolved and fund 2 slashed
+        for _ in 0..3 {
+            assert_ok!(create_fund(1, 2, 1000, 10));
+        }
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(3),
+            0,
+            1000,
+            Some(7)
+        ));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(3),
+            1,
+            100,
+            Some(7)
+        ));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(3),
+            2,
+            100,
+            Some(7)
+        ));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(4),
+            2,
+            100,
+            Some(8)
+        ));
+        assert!(crate::ContributionRefs::<Test>::contains_key(2, (4, 8)));
+
+        assert_ok!(PalletCrowdfund::report_and_slash(Origin::root(), 2));
+        assert_eq!(crate::ContributionRefs::<Test>::iter_prefix(2).count(), 0);
+
+        System::set_block_number(11);
+        assert_ok!(PalletCrowdfund::dispense(Origin::signed(1), 0));
+        assert_eq!(crate::ContributionRefs::<Test>::iter_prefix(0).count(), 0);
+        assert_eq!(crate::ContributionRefs::<Test>::iter_prefix(1).count(), 1);
+
+        System::set_block_number(20);
+        assert_ok!(PalletCrowdfund::dissolve(Origin::signed(1), 1));
+        assert_eq!(crate::ContributionRefs::<Test>::iter().count(), 0);
+    });
+}
+
+#[test]
+fn cooldown_record_is_pruned_once_it_has_passed() {
+    new_test_ext().execute_with(|| {
+        CREATION_COOLDOWN.with(|cooldown| *cooldown.borrow_mut() = 5);
+        assert_ok!(create_fund(1, 2, 1000, 3));
+        assert_ok!(PalletCrowdfund::contribute(
+            Origin::signed(3),
+            0,
+            1000,
+            None
+        ));
+
+        // Still cooling down when the fund is removed, so the record stays
+        System::set_block_number(4);
+        assert_ok!(PalletCrowdfund::dispense(Origin::signed(1), 0));
+        assert_eq!(PalletCrowdfund::last_created(1), Some(1));
+
+        System::set_block_number(6);
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        System::set_block_number(11);
+        assert_ok!(PalletCrowdfund::report_and_slash(Origin::root(), 1));
+        assert_eq!(PalletCrowdfund::last_created(1), None);
+        assert_ok!(create_fund(1, 2, 1000, 20));
+    });
+}
+
+#[test]
+fn dissolve_charges_for_the_entries_it_clears() {
+    new_test_ext().execute_with(|| {
+        let declared = crate::Call::<Test>::dissolve(0).get_dispatch_info().weight;
+        assert_eq!(declared, MockWeights::dissolve(MaxContributors::get()));
+
+        assert_ok!(create_fund(1, 2, 1000, 10));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(3), 0, 100, None));
+        assert_ok!(PalletCrowdfund::contribute(Origin::signed(4), 0, 100, None));
+
+        System::set_block_number(20);
+        let info = PalletCrowdfund::dissolve(Origin::signed(5), 0).unwrap();
+        assert_eq!(info.actual_weight, Some(MockWeights::dissolve(2)));
+    });
+}
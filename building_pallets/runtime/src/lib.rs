@@ -12,7 +12,7 @@ use sp_api::impl_runtime_apis;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::traits::{
-    AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, Verify,
+    AccountIdLookup, BlakeTwo256, Block as BlockT, ConvertInto, IdentifyAccount, NumberFor, Verify,
 };
 use sp_runtime::{
     create_runtime_str, generic, impl_opaque_keys,
@@ -267,17 +267,55 @@ impl pallet_sudo::Config for Runtime {
 }
 
 parameter_types! {
-    pub const SubmissionDeposit: u64 = 10000;
-    pub const MinContribution: u64 = 100;
-    pub const RetirementPeriod: u32 = 10;
+    pub const MinVestedTransfer: Balance = 100;
+}
+
+impl pallet_vesting::Config for Runtime {
+    type Event = Event;
+    type Currency = Balances;
+    type BlockNumberToBalance = ConvertInto;
+    type MinVestedTransfer = MinVestedTransfer;
+    type WeightInfo = pallet_vesting::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+    pub const SubmissionDeposit: Balance = 10000;
+    pub const MinContribution: Balance = 100;
+    pub const MinGoal: Balance = 1_000;
+    pub const CreationCooldown: BlockNumber = HOURS;
+    pub const MaxDuration: BlockNumber = 90 * DAYS;
+    pub const RetirementPeriod: BlockNumber = 10;
+    pub const ContributionFee: Perbill = Perbill::from_percent(0);
+    pub const MaxSplits: u32 = 4;
+    pub const AllowContributorDeath: bool = false;
+    pub const AllowContributionsWhileFrozen: bool = false;
+    pub const MaxStretchGoals: u32 = 8;
+    pub const MaxContributors: u32 = 1_000;
+    pub const MaxAutoDispense: u32 = 10;
 }
 
 impl pallet_crowdfund::Config for Runtime {
     type Event = Event;
     type Currency = Balances;
-    type SubmissionDeposit = SubmissionDeposit;
+    type DepositCalculator = pallet_crowdfund::FixedDeposit<SubmissionDeposit>;
     type MinContribution = MinContribution;
+    type MinGoal = MinGoal;
+    type AllowContributorDeath = AllowContributorDeath;
+    type AllowContributionsWhileFrozen = AllowContributionsWhileFrozen;
+    type CreationCooldown = CreationCooldown;
+    type MaxDuration = MaxDuration;
     type RetirementPeriod = RetirementPeriod;
+    type ContributionFee = ContributionFee;
+    type MaxSplits = MaxSplits;
+    type MaxStretchGoals = MaxStretchGoals;
+    type VestingSchedule = Vesting;
+    type OnSuccess = ();
+    type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+    type OnSlash = ();
+    type DissolveDestination = pallet_crowdfund::RewardDissolver<Balances>;
+    type MaxContributors = MaxContributors;
+    type MaxAutoDispense = MaxAutoDispense;
+    type WeightInfo = pallet_crowdfund::weights::SubstrateWeight<Runtime>;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -295,6 +333,7 @@ construct_runtime!(
         Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
         TransactionPayment: pallet_transaction_payment::{Module, Storage},
         Sudo: pallet_sudo::{Module, Call, Config<T>, Storage, Event<T>},
+        Vesting: pallet_vesting::{Module, Call, Storage, Event<T>},
         PalletCrowdfund: pallet_crowdfund::{Module, Call, Storage, Event<T>},
     }
 );
@@ -462,6 +501,54 @@ impl_runtime_apis! {
         }
     }
 
+    impl pallet_crowdfund_runtime_api::CrowdfundApi<Block, AccountId, Balance, BlockNumber> for Runtime {
+        fn goal_progress(index: pallet_crowdfund::FundIndex) -> Option<Perbill> {
+            PalletCrowdfund::goal_progress(index)
+        }
+
+        fn validate_create(goal: Balance, end: BlockNumber) -> Result<(), sp_runtime::DispatchError> {
+            PalletCrowdfund::validate_create(goal, end)
+        }
+
+        fn blocks_until_end(index: pallet_crowdfund::FundIndex) -> Option<BlockNumber> {
+            PalletCrowdfund::blocks_until_end(index)
+        }
+
+        fn blocks_until_dissolvable(index: pallet_crowdfund::FundIndex) -> Option<BlockNumber> {
+            PalletCrowdfund::blocks_until_dissolvable(index)
+        }
+
+        fn raised(index: pallet_crowdfund::FundIndex) -> Balance {
+            PalletCrowdfund::raised(index)
+        }
+
+        fn active_funds(
+            start: pallet_crowdfund::FundIndex,
+            limit: u32,
+        ) -> (
+            Vec<(pallet_crowdfund::FundIndex, pallet_crowdfund::FundInfo<AccountId, Balance, BlockNumber>)>,
+            Option<pallet_crowdfund::FundIndex>,
+        ) {
+            PalletCrowdfund::active_funds(start, limit)
+        }
+
+        fn funds_in_category(category: pallet_crowdfund::Category) -> Vec<pallet_crowdfund::FundIndex> {
+            PalletCrowdfund::funds_in_category(category)
+        }
+
+        fn has_contributed(index: pallet_crowdfund::FundIndex, who: AccountId) -> bool {
+            PalletCrowdfund::has_contributed(index, &who)
+        }
+
+        fn child_trie_key(index: pallet_crowdfund::FundIndex) -> Vec<u8> {
+            PalletCrowdfund::child_trie_key(index)
+        }
+
+        fn refund_limit(remaining: u64) -> u32 {
+            PalletCrowdfund::refund_limit(remaining)
+        }
+    }
+
     #[cfg(feature = "runtime-benchmarks")]
     impl frame_benchmarking::Benchmark<Block> for Runtime {
         fn dispatch_benchmark(
@@ -491,7 +578,7 @@ impl_runtime_apis! {
             add_benchmark!(params, batches, frame_system, SystemBench::<Runtime>);
             add_benchmark!(params, batches, pallet_balances, Balances);
             add_benchmark!(params, batches, pallet_timestamp, Timestamp);
-            add_benchmark!(params, batches, pallet_template, TemplateModule);
+            add_benchmark!(params, batches, pallet_crowdfund, PalletCrowdfund);
 
             if batches.is_empty() { return Err("Benchmark not found for this pallet.".into()) }
             Ok(batches)